@@ -0,0 +1,185 @@
+//! Pre-seeds air-gapped registries with the images of an enumeration.
+//!
+//! A [MirrorManifest] pairs each distinct upstream image with its mirror reference
+//! according to a [Mirrors] table and renders
+//!
+//! - a `skopeo copy` script,
+//! - an image list with one `source=target` mapping per line
+//!   (the format of `oc image mirror --filename`),
+//! - a `registries.conf` snippet, which lets Podman pull the upstream names from the mirrors.
+//!
+//! Images are copied with all platforms and their digests preserved, so images pinned by
+//! digest resolve on the mirror as well.
+
+use crate::image::DEFAULT_PLATFORM;
+use crate::mirror::Mirrors;
+use crate::shell::command_line;
+use crate::yaml::quote;
+use crate::WildFlyContainer;
+use anyhow::{bail, Result};
+
+/// An image and its mirror
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MirrorImage {
+    /// The upstream image like "quay.io/wildfly/wildfly:26.1.3.Final-jdk17"
+    pub source: String,
+
+    /// The mirror reference like "mirror.corp/wildfly/wildfly:26.1.3.Final-jdk17"
+    pub target: String,
+
+    /// The platforms of the image
+    pub platforms: Vec<String>,
+}
+
+/// The images to mirror and the rules which map them
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MirrorManifest {
+    /// The distinct images in order of appearance
+    pub images: Vec<MirrorImage>,
+
+    /// The (source, target) prefixes of all rules in use
+    pub rules: Vec<(String, String)>,
+}
+
+impl MirrorManifest {
+    /// Renders a shell script which copies all images with `skopeo`.
+    pub fn script(&self) -> String {
+        let mut lines = vec![
+            "#!/bin/sh".to_string(),
+            "set -eu".to_string(),
+            String::new(),
+        ];
+        for image in &self.images {
+            // without --all, skopeo copies one platform of an index, which changes the digest
+            let mut copy = vec!["skopeo", "copy", "--all", "--preserve-digests"];
+            let source = format!("docker://{}", image.source);
+            let target = format!("docker://{}", image.target);
+            copy.extend([source.as_str(), target.as_str()]);
+            lines.push(command_line(&copy));
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    /// Renders the image list with one `source=target` mapping per line.
+    pub fn image_list(&self) -> String {
+        self.images
+            .iter()
+            .map(|image| format!("{}={}\n", image.source, image.target))
+            .collect()
+    }
+
+    /// Renders the `[[registry]]` tables for `registries.conf`.
+    pub fn registries_conf(&self) -> String {
+        self.rules
+            .iter()
+            .map(|(source, target)| {
+                format!(
+                    "[[registry]]\nprefix = {}\nlocation = {}\n\n[[registry.mirror]]\nlocation = {}\n",
+                    quote(source),
+                    quote(source),
+                    quote(target)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Collects the distinct images of the given containers and their mirrors.
+///
+/// Dev builds and local distributions have no image and are skipped.
+/// Fails if no rule of the table matches an image.
+pub fn mirror_manifest(
+    containers: &[WildFlyContainer],
+    mirrors: &Mirrors,
+) -> Result<MirrorManifest> {
+    let mut images: Vec<MirrorImage> = vec![];
+    let mut rules: Vec<(String, String)> = vec![];
+    for wildfly in containers.iter().filter(|w| w.has_image()) {
+        let source = wildfly.upstream_image_name();
+        if images.iter().any(|image| image.source == source) {
+            continue;
+        }
+        let Some((from, to)) = mirrors.rule_for(&source) else {
+            bail!("no mirror for {}", source)
+        };
+        let rule = (from.to_string(), to.to_string());
+        if !rules.contains(&rule) {
+            rules.push(rule);
+        }
+        images.push(MirrorImage {
+            target: mirrors.rewrite(&source),
+            source,
+            platforms: if wildfly.platforms.is_empty() {
+                vec![DEFAULT_PLATFORM.to_string()]
+            } else {
+                wildfly.platforms.clone()
+            },
+        });
+    }
+    Ok(MirrorManifest { images, rules })
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod airgap_tests {
+    use crate::airgap::mirror_manifest;
+    use crate::mirror::Mirrors;
+    use crate::WildFlyContainer;
+
+    fn mirrors() -> Mirrors {
+        Mirrors::default()
+            .with("quay.io/wildfly", "mirror.corp/wildfly")
+            .with("docker.io/jboss", "mirror.corp/jboss")
+    }
+
+    #[test]
+    fn manifest() {
+        let containers = WildFlyContainer::enumeration("3x20,26.1,2x39,dev").unwrap();
+        let manifest = mirror_manifest(&containers, &mirrors()).unwrap();
+        assert_eq!(3, manifest.images.len());
+        assert_eq!(
+            r#"#!/bin/sh
+set -eu
+
+skopeo copy --all --preserve-digests docker://docker.io/jboss/wildfly:20.0.1.Final docker://mirror.corp/jboss/wildfly:20.0.1.Final
+skopeo copy --all --preserve-digests docker://quay.io/wildfly/wildfly:26.1.3.Final-jdk17 docker://mirror.corp/wildfly/wildfly:26.1.3.Final-jdk17
+skopeo copy --all --preserve-digests docker://quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21 docker://mirror.corp/wildfly/wildfly:39.0.1.Final-2-jdk21
+"#,
+            manifest.script()
+        );
+        assert_eq!(
+            r#"docker.io/jboss/wildfly:20.0.1.Final=mirror.corp/jboss/wildfly:20.0.1.Final
+quay.io/wildfly/wildfly:26.1.3.Final-jdk17=mirror.corp/wildfly/wildfly:26.1.3.Final-jdk17
+quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21=mirror.corp/wildfly/wildfly:39.0.1.Final-2-jdk21
+"#,
+            manifest.image_list()
+        );
+        assert_eq!(
+            r#"[[registry]]
+prefix = "docker.io/jboss"
+location = "docker.io/jboss"
+
+[[registry.mirror]]
+location = "mirror.corp/jboss"
+
+[[registry]]
+prefix = "quay.io/wildfly"
+location = "quay.io/wildfly"
+
+[[registry.mirror]]
+location = "mirror.corp/wildfly"
+"#,
+            manifest.registries_conf()
+        );
+    }
+
+    #[test]
+    fn unmapped() {
+        let containers = WildFlyContainer::enumeration("20,26.1").unwrap();
+        let mirrors = Mirrors::default().with("quay.io/wildfly", "mirror.corp/wildfly");
+        assert!(mirror_manifest(&containers, &mirrors).is_err());
+    }
+}
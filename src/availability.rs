@@ -0,0 +1,251 @@
+//! Checks whether the host ports of [WildFlyInstance]s are available on localhost.
+//!
+//! [check] computes the ports of all instances using a [PortStrategy], tries to bind
+//! each of them and reports ports which are already in use or which are assigned to
+//! more than one instance. [PortReport::suggest] shifts the conflicting instances to
+//! find alternative ports.
+
+use crate::instance::WildFlyInstance;
+use crate::ports::{PortMapping, PortStrategy, Ports};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{Ipv4Addr, TcpListener};
+
+/// Why a port cannot be used
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConflictReason {
+    /// Binding the port on localhost failed, e.g. because it's already in use.
+    Unavailable(io::ErrorKind),
+
+    /// The port is already assigned to another instance (contains the name of the other instance).
+    Duplicate(String),
+}
+
+/// A port which cannot be used by an instance
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PortConflict {
+    /// The instance
+    pub instance: WildFlyInstance,
+
+    /// The socket binding, host and container port
+    pub mapping: PortMapping,
+
+    /// Why the port cannot be used
+    pub reason: ConflictReason,
+}
+
+/// Alternative ports for an instance with conflicts
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Suggestion {
+    /// The instance
+    pub instance: WildFlyInstance,
+
+    /// The number of instance indexes the ports are shifted by, e.g. 1 for the ports
+    /// of the second instance instead of the first one
+    pub shift: u16,
+
+    /// The ports which are currently available
+    pub ports: Ports,
+}
+
+/// The result of [check]
+#[derive(Debug, Clone, Default)]
+pub struct PortReport {
+    /// The instances and their allocated ports
+    pub allocated: Vec<(WildFlyInstance, Ports)>,
+
+    /// The ports which cannot be used
+    pub conflicts: Vec<PortConflict>,
+}
+
+impl PortReport {
+    /// Returns `true` if all ports are available.
+    pub fn is_ok(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// Returns the instances with at least one conflict in the order of the enumeration.
+    pub fn conflicting_instances(&self) -> Vec<&WildFlyInstance> {
+        let mut seen = HashSet::new();
+        self.conflicts
+            .iter()
+            .map(|conflict| &conflict.instance)
+            .filter(|instance| seen.insert(instance.name()))
+            .collect()
+    }
+
+    /// Asks the given strategy for alternative ports of all conflicting instances.
+    ///
+    /// For each conflicting instance, the strategy is asked up to `attempts` times for the
+    /// ports of the instance shifted by 0, 1, 2, ... indexes. So deterministic strategies
+    /// like [VersionBased](crate::ports::VersionBased) suggest the ports of a higher
+    /// instance band, while [Ephemeral](crate::ports::Ephemeral) suggests new ports on
+    /// every attempt. The first set of ports which is available and doesn't clash with
+    /// the ports of other instances (including previous suggestions) is taken. Instances
+    /// without a suggestion are omitted.
+    pub fn suggest(&self, strategy: &dyn PortStrategy, attempts: usize) -> Vec<Suggestion> {
+        let conflicting = self
+            .conflicting_instances()
+            .into_iter()
+            .map(|instance| instance.name())
+            .collect::<HashSet<_>>();
+        let mut used = self
+            .allocated
+            .iter()
+            .filter(|(instance, _)| !conflicting.contains(&instance.name()))
+            .flat_map(|(_, ports)| ports.to_array())
+            .collect::<HashSet<_>>();
+        let mut suggestions = vec![];
+        for instance in self.conflicting_instances() {
+            for shift in (0..attempts).map_while(|shift| u16::try_from(shift).ok()) {
+                let Some(index) = instance.index.checked_add(shift) else {
+                    break;
+                };
+                let shifted = WildFlyInstance::new(instance.wildfly.clone(), index);
+                let Ok(ports) = strategy.instance_ports(&shifted) else {
+                    break;
+                };
+                let candidates = ports.to_array();
+                let distinct = candidates.iter().collect::<HashSet<_>>().len() == candidates.len();
+                if distinct
+                    && candidates.iter().all(|port| !used.contains(port))
+                    && candidates.iter().all(|port| bind(*port).is_ok())
+                {
+                    used.extend(candidates);
+                    suggestions.push(Suggestion {
+                        instance: instance.clone(),
+                        shift,
+                        ports,
+                    });
+                    break;
+                }
+            }
+        }
+        suggestions
+    }
+}
+
+/// Computes the ports of the given instances and tries to bind each of them on localhost.
+///
+/// Returns an error if the strategy fails to allocate ports for an instance.
+pub fn check(instances: &[WildFlyInstance], strategy: &dyn PortStrategy) -> Result<PortReport> {
+    let mut report = PortReport::default();
+    let mut owners: HashMap<u16, String> = HashMap::new();
+    for instance in instances {
+        let ports = strategy.instance_ports(instance)?;
+        for mapping in ports.mappings() {
+            let reason = if let Some(owner) = owners.get(&mapping.host) {
+                Some(ConflictReason::Duplicate(owner.clone()))
+            } else {
+                owners.insert(mapping.host, instance.name());
+                bind(mapping.host).err().map(ConflictReason::Unavailable)
+            };
+            if let Some(reason) = reason {
+                report.conflicts.push(PortConflict {
+                    instance: instance.clone(),
+                    mapping,
+                    reason,
+                });
+            }
+        }
+        report.allocated.push((instance.clone(), ports));
+    }
+    Ok(report)
+}
+
+fn bind(port: u16) -> Result<(), io::ErrorKind> {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .map(|_| ())
+        .map_err(|e| e.kind())
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod availability_tests {
+    use crate::availability::{check, ConflictReason};
+    use crate::instance::WildFlyInstance;
+    use crate::ports::{Ephemeral, PortStrategy, Ports};
+    use crate::testing::Fixed;
+    use crate::WildFlyContainer;
+    use std::io;
+    use std::net::{Ipv4Addr, TcpListener};
+
+    fn free_ports() -> Ports {
+        Ephemeral
+            .ports(&WildFlyContainer::version("26.1").unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn all_available() {
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        let report = check(&instances, &Fixed(free_ports())).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(1, report.allocated.len());
+    }
+
+    #[test]
+    fn in_use() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let ports = Ports {
+            management: listener.local_addr().unwrap().port(),
+            ..free_ports()
+        };
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        let report = check(&instances, &Fixed(ports)).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(1, report.conflicts.len());
+        assert_eq!("management", report.conflicts[0].mapping.name);
+        assert_eq!(
+            ConflictReason::Unavailable(io::ErrorKind::AddrInUse),
+            report.conflicts[0].reason
+        );
+    }
+
+    #[test]
+    fn duplicates() {
+        let instances = WildFlyInstance::enumeration("2x26.1").unwrap();
+        let report = check(&instances, &Fixed(free_ports())).unwrap();
+        assert_eq!(6, report.conflicts.len());
+        assert!(report.conflicts.iter().all(|c| c.instance.index == 1
+            && c.reason == ConflictReason::Duplicate("wildfly-26.1".to_string())));
+        assert_eq!(1, report.conflicting_instances().len());
+    }
+
+    #[test]
+    fn suggestions() {
+        let instances = WildFlyInstance::enumeration("2x26.1").unwrap();
+        let fixed = free_ports();
+        let report = check(&instances, &Fixed(fixed)).unwrap();
+        let suggestions = report.suggest(&Ephemeral, 3);
+        assert_eq!(1, suggestions.len());
+        assert_eq!(1, suggestions[0].instance.index);
+        assert!(suggestions[0]
+            .ports
+            .to_array()
+            .iter()
+            .all(|port| !fixed.to_array().contains(port)));
+        assert!(report.suggest(&Fixed(fixed), 3).is_empty());
+    }
+
+    #[test]
+    fn fixed_suggestions() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let ports = Ports {
+            http: listener.local_addr().unwrap().port(),
+            ..free_ports()
+        };
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        let report = check(&instances, &Fixed(ports)).unwrap();
+        assert_eq!(1, report.conflicts.len());
+        let alternative = free_ports();
+        let suggestions = report.suggest(&Fixed(alternative), 3);
+        assert_eq!(1, suggestions.len());
+        assert_eq!(0, suggestions[0].instance.index);
+        assert_eq!(0, suggestions[0].shift);
+        assert_eq!(alternative, suggestions[0].ports);
+        assert!(report.suggest(&Fixed(ports), 3).is_empty());
+    }
+}
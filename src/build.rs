@@ -0,0 +1,398 @@
+//! Plans how to build the image of a development (build-from-source) container.
+//!
+//! A [BuildPlan] lists the [BuildStep]s as data, so tools can run them one by one,
+//! and renders them as a POSIX shell script:
+//!
+//! 1. clone the repository of the [DevBuild] at its reference
+//! 2. build WildFly with Maven
+//! 3. copy the distribution from `dist/target/wildfly-*` to the build context
+//! 4. write a Containerfile which installs the distribution to [WILDFLY_HOME]
+//! 5. build the image
+//!
+//! The image uses the same layout as the stock images. [BuildPlan::wildfly] references the
+//! resulting image, so the generators of this crate (e.g. [crate::containerfile]) work for
+//! development builds once the plan has run.
+
+use crate::containerfile::quote_value;
+use crate::dev::{DevBuild, GitRef};
+use crate::ports::CONTAINER_PORTS;
+use crate::run::{standalone_command, Engine, WILDFLY_HOME};
+use crate::shell::{command_line, quote};
+use crate::yaml::flow_sequence;
+use crate::{mirror, WildFlyContainer};
+use anyhow::{bail, Result};
+
+/// The base image with the JDK used to run development builds
+pub const DEFAULT_BASE_IMAGE: &str = "docker.io/library/eclipse-temurin:21-jdk";
+
+/// Options for the build plan
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BuildOptions {
+    /// The directory to clone the sources into
+    pub directory: String,
+
+    /// The Maven profiles to activate
+    pub profiles: Vec<String>,
+
+    /// Additional Maven arguments
+    pub maven_args: Vec<String>,
+
+    /// The build context of the image
+    pub context: String,
+
+    /// The base image which provides the JDK (rewritten to the configured mirrors)
+    pub base_image: String,
+
+    /// The engine which builds the image
+    pub engine: Engine,
+
+    /// The image name, defaults to `localhost/wildfly:<display version>`
+    pub image: Option<String>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            directory: "wildfly".to_string(),
+            profiles: vec![],
+            maven_args: vec!["-DskipTests".to_string()],
+            context: "context".to_string(),
+            base_image: DEFAULT_BASE_IMAGE.to_string(),
+            engine: Engine::Podman,
+            image: None,
+        }
+    }
+}
+
+/// One step of a [BuildPlan]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BuildStep {
+    /// Clones the repository at the given reference into a directory
+    Clone {
+        repository: String,
+        reference: GitRef,
+        directory: String,
+    },
+
+    /// Runs the Maven wrapper in the source directory
+    Maven {
+        directory: String,
+        profiles: Vec<String>,
+        args: Vec<String>,
+    },
+
+    /// Copies the first directory in `search` matching `pattern` to `target`
+    Distribution {
+        search: String,
+        pattern: String,
+        target: String,
+    },
+
+    /// Writes a Containerfile
+    Containerfile { path: String, content: String },
+
+    /// Builds the image from the build context
+    Image {
+        engine: Engine,
+        image: String,
+        context: String,
+        containerfile: String,
+    },
+}
+
+impl BuildStep {
+    /// Renders the step as shell commands.
+    pub fn script(&self) -> Vec<String> {
+        match self {
+            BuildStep::Clone {
+                repository,
+                reference,
+                directory,
+            } => match reference {
+                GitRef::Branch(name) | GitRef::Tag(name) => vec![command_line(&[
+                    "git", "clone", "--depth", "1", "--branch", name, repository, directory,
+                ])],
+                GitRef::PullRequest(_) => vec![
+                    command_line(&["git", "clone", "--depth", "1", repository, directory]),
+                    command_line(&[
+                        "git",
+                        "-C",
+                        directory,
+                        "fetch",
+                        "--depth",
+                        "1",
+                        "origin",
+                        &reference.fetch_name(),
+                    ]),
+                    command_line(&["git", "-C", directory, "checkout", "FETCH_HEAD"]),
+                ],
+            },
+            BuildStep::Maven {
+                directory,
+                profiles,
+                args,
+            } => {
+                let mut mvn = vec![
+                    "./mvnw".to_string(),
+                    "-B".to_string(),
+                    "install".to_string(),
+                ];
+                if !profiles.is_empty() {
+                    mvn.push(format!("-P{}", profiles.join(",")));
+                }
+                mvn.extend(args.iter().cloned());
+                vec![format!(
+                    "(cd {} && {})",
+                    quote(directory),
+                    command_line(&mvn)
+                )]
+            }
+            BuildStep::Distribution {
+                search,
+                pattern,
+                target,
+            } => vec![
+                format!(
+                    "DIST=$({} | head -n 1)",
+                    command_line(&[
+                        "find",
+                        search,
+                        "-mindepth",
+                        "1",
+                        "-maxdepth",
+                        "1",
+                        "-type",
+                        "d",
+                        "-name",
+                        pattern,
+                    ])
+                ),
+                format!(
+                    "test -n \"$DIST\" || {{ echo {} >&2; exit 1; }}",
+                    quote(&format!("no distribution {} in {}", pattern, search))
+                ),
+                command_line(&["rm", "-rf", target]),
+                format!("mkdir -p \"$(dirname {})\"", quote(target)),
+                format!("cp -R \"$DIST\" {}", quote(target)),
+            ],
+            BuildStep::Containerfile { path, content } => {
+                let mut lines = vec![format!("cat > {} <<'EOF'", quote(path))];
+                lines.extend(content.lines().map(str::to_string));
+                lines.push("EOF".to_string());
+                lines
+            }
+            BuildStep::Image {
+                engine,
+                image,
+                context,
+                containerfile,
+            } => vec![command_line(&[
+                engine.command(),
+                "build",
+                "-t",
+                image,
+                "-f",
+                containerfile,
+                context,
+            ])],
+        }
+    }
+}
+
+/// The steps to build the image of a development container
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BuildPlan {
+    /// The name of the resulting image
+    pub image: String,
+
+    /// The development container with the resulting image as custom image
+    pub wildfly: WildFlyContainer,
+
+    /// The steps in order
+    pub steps: Vec<BuildStep>,
+}
+
+impl BuildPlan {
+    /// Renders the plan as shell script, which stops at the first failing command.
+    pub fn script(&self) -> String {
+        let mut lines = vec!["#!/bin/sh".to_string(), "set -eu".to_string()];
+        for step in &self.steps {
+            lines.push(String::new());
+            lines.extend(step.script());
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+/// Plans the build of a development container.
+///
+/// If the [DevBuild] knows the expected version, only a matching distribution is accepted.
+/// Fails for released versions.
+pub fn build_plan(wildfly: &WildFlyContainer, options: &BuildOptions) -> Result<BuildPlan> {
+    let Some(build) = &wildfly.dev else {
+        bail!("{} is not a development build", wildfly.display_version())
+    };
+    mirror::validate()?;
+    let image = options
+        .image
+        .clone()
+        .unwrap_or_else(|| format!("localhost/wildfly:{}", wildfly.display_version()));
+    let distribution = format!("{}/wildfly", options.context);
+    let containerfile = format!("{}/Containerfile", options.context);
+    Ok(BuildPlan {
+        image: image.clone(),
+        wildfly: WildFlyContainer {
+            custom_image: Some(image.clone()),
+            ..wildfly.clone()
+        },
+        steps: vec![
+            BuildStep::Clone {
+                repository: build.repository.clone(),
+                reference: build.reference.clone(),
+                directory: options.directory.clone(),
+            },
+            BuildStep::Maven {
+                directory: options.directory.clone(),
+                profiles: options.profiles.clone(),
+                args: options.maven_args.clone(),
+            },
+            BuildStep::Distribution {
+                search: format!("{}/dist/target", options.directory),
+                pattern: match &build.expected_version {
+                    Some(version) => format!("wildfly-{}*", version),
+                    None => "wildfly-*".to_string(),
+                },
+                target: distribution,
+            },
+            BuildStep::Containerfile {
+                path: containerfile.clone(),
+                content: containerfile_content(build, &options.base_image),
+            },
+            BuildStep::Image {
+                engine: options.engine,
+                image,
+                context: options.context.clone(),
+                containerfile,
+            },
+        ],
+    })
+}
+
+fn containerfile_content(build: &DevBuild, base_image: &str) -> String {
+    [
+        format!("FROM {}", mirror::rewrite(base_image)),
+        format!(
+            "LABEL org.opencontainers.image.source={} org.opencontainers.image.revision={}",
+            quote_value(&build.repository),
+            quote_value(&build.reference.fetch_name())
+        ),
+        "RUN groupadd -r jboss && useradd -r -g jboss -m -d /opt/jboss jboss".to_string(),
+        format!(
+            "COPY --chown=jboss:jboss {}",
+            flow_sequence(&["wildfly", WILDFLY_HOME])
+        ),
+        format!("ENV JBOSS_HOME={}", WILDFLY_HOME),
+        "USER jboss".to_string(),
+        format!(
+            "EXPOSE {} {}",
+            CONTAINER_PORTS.http, CONTAINER_PORTS.management
+        ),
+        format!("CMD {}", flow_sequence(&standalone_command())),
+        String::new(),
+    ]
+    .join("\n")
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod build_tests {
+    use crate::build::{build_plan, BuildOptions, BuildStep};
+    use crate::containerfile::{containerfile, Customization};
+    use crate::WildFlyContainer;
+
+    #[test]
+    fn main_branch() {
+        let dev = WildFlyContainer::version("dev").unwrap();
+        let plan = build_plan(&dev, &BuildOptions::default()).unwrap();
+        assert_eq!("localhost/wildfly:dev", plan.image);
+        assert_eq!(5, plan.steps.len());
+        assert!(plan.wildfly.is_dev());
+        assert_eq!("localhost/wildfly:dev", plan.wildfly.image_name());
+        assert_eq!(dev.http_port().unwrap(), plan.wildfly.http_port().unwrap());
+        assert!(containerfile(&plan.wildfly, &Customization::default()).is_ok());
+        assert_eq!(
+            r#"#!/bin/sh
+set -eu
+
+git clone --depth 1 --branch main https://github.com/wildfly/wildfly.git wildfly
+
+(cd wildfly && ./mvnw -B install -DskipTests)
+
+DIST=$(find wildfly/dist/target -mindepth 1 -maxdepth 1 -type d -name 'wildfly-*' | head -n 1)
+test -n "$DIST" || { echo 'no distribution wildfly-* in wildfly/dist/target' >&2; exit 1; }
+rm -rf context/wildfly
+mkdir -p "$(dirname context/wildfly)"
+cp -R "$DIST" context/wildfly
+
+cat > context/Containerfile <<'EOF'
+FROM docker.io/library/eclipse-temurin:21-jdk
+LABEL org.opencontainers.image.source="https://github.com/wildfly/wildfly.git" org.opencontainers.image.revision="main"
+RUN groupadd -r jboss && useradd -r -g jboss -m -d /opt/jboss jboss
+COPY --chown=jboss:jboss ["wildfly", "/opt/jboss/wildfly"]
+ENV JBOSS_HOME=/opt/jboss/wildfly
+USER jboss
+EXPOSE 8080 9990
+CMD ["/opt/jboss/wildfly/bin/standalone.sh", "-b", "0.0.0.0", "-bmanagement", "0.0.0.0"]
+EOF
+
+podman build -t localhost/wildfly:dev -f context/Containerfile context
+"#,
+            plan.script()
+        );
+    }
+
+    #[test]
+    fn pull_request() {
+        let dev = WildFlyContainer::version("dev#12345").unwrap();
+        let options = BuildOptions {
+            profiles: vec!["release".to_string()],
+            ..BuildOptions::default()
+        };
+        let plan = build_plan(&dev, &options).unwrap();
+        let script = plan.script();
+        assert!(script.contains(
+            "git clone --depth 1 https://github.com/wildfly/wildfly.git wildfly\n\
+             git -C wildfly fetch --depth 1 origin pull/12345/head\n\
+             git -C wildfly checkout FETCH_HEAD\n"
+        ));
+        assert!(script.contains("./mvnw -B install -Prelease -DskipTests"));
+    }
+
+    #[test]
+    fn label_quoting() {
+        let dev = WildFlyContainer::version("dev@myfork/cost-$HOME").unwrap();
+        let plan = build_plan(&dev, &BuildOptions::default()).unwrap();
+        assert!(plan
+            .script()
+            .contains(r#"org.opencontainers.image.revision="cost-\$HOME""#));
+    }
+
+    #[test]
+    fn expected_version() {
+        let dev = WildFlyContainer::version("dev@31.0.0.Final").unwrap();
+        let plan = build_plan(&dev, &BuildOptions::default()).unwrap();
+        assert!(plan.steps.contains(&BuildStep::Distribution {
+            search: "wildfly/dist/target".to_string(),
+            pattern: "wildfly-31.0.0*".to_string(),
+            target: "context/wildfly".to_string(),
+        }));
+    }
+
+    #[test]
+    fn release_has_no_plan() {
+        let wildfly = WildFlyContainer::version("39").unwrap();
+        assert!(build_plan(&wildfly, &BuildOptions::default()).is_err());
+    }
+}
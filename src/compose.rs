@@ -0,0 +1,203 @@
+//! Generates a Compose file for [WildFlyInstance]s.
+//!
+//! Each instance becomes one service named after [WildFlyInstance::name], with the
+//! port mappings, environment and command of its [RunSpec]. All services join a
+//! shared network and can optionally define a healthcheck.
+
+use crate::instance::WildFlyInstance;
+use crate::ports::{PortStrategy, CONTAINER_PORTS};
+use crate::run::{run_specs, RunSpec};
+use crate::yaml::{flow_sequence, quote};
+use anyhow::Result;
+
+/// A healthcheck, which is run inside each container
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Healthcheck {
+    /// The command, passed to the shell (`CMD-SHELL`)
+    pub command: String,
+
+    /// Duration like "10s" between two checks
+    pub interval: String,
+
+    /// Duration like "5s" after which a check fails
+    pub timeout: String,
+
+    /// Number of consecutive failures until the container is unhealthy
+    pub retries: u32,
+}
+
+impl Default for Healthcheck {
+    /// Requests the welcome page, which works for all WildFly versions.
+    fn default() -> Self {
+        Self {
+            command: format!(
+                "curl -sf -o /dev/null http://localhost:{}/ || exit 1",
+                CONTAINER_PORTS.http
+            ),
+            interval: "10s".to_string(),
+            timeout: "5s".to_string(),
+            retries: 30,
+        }
+    }
+}
+
+/// Options for the generated Compose file
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ComposeOptions {
+    /// An optional project name
+    pub project: Option<String>,
+
+    /// The name of the network shared by all services
+    pub network: String,
+
+    /// An optional healthcheck added to all services
+    pub healthcheck: Option<Healthcheck>,
+}
+
+impl Default for ComposeOptions {
+    fn default() -> Self {
+        Self {
+            project: None,
+            network: "wildfly".to_string(),
+            healthcheck: None,
+        }
+    }
+}
+
+/// Renders a Compose file with one service per instance.
+///
+/// Fails for dev builds, local distributions and if the strategy fails to allocate ports.
+pub fn compose(
+    instances: &[WildFlyInstance],
+    strategy: &dyn PortStrategy,
+    options: &ComposeOptions,
+) -> Result<String> {
+    Ok(compose_specs(&run_specs(instances, strategy)?, options))
+}
+
+/// Renders a Compose file with one service per run specification.
+///
+/// Extra engine arguments ([RunSpec::args]) have no equivalent in Compose and are ignored.
+pub fn compose_specs(specs: &[RunSpec], options: &ComposeOptions) -> String {
+    let mut lines = vec![];
+    if let Some(project) = &options.project {
+        lines.push(format!("name: {}", quote(project)));
+    }
+    lines.push("services:".to_string());
+    for spec in specs {
+        lines.push(format!("  {}:", quote(&spec.name)));
+        lines.push(format!("    image: {}", quote(&spec.image)));
+        lines.push(format!("    container_name: {}", quote(&spec.name)));
+        if let Some(platform) = &spec.platform {
+            lines.push(format!("    platform: {}", quote(platform)));
+        }
+        let mappings = spec.mappings();
+        if !mappings.is_empty() {
+            lines.push("    ports:".to_string());
+            for mapping in mappings {
+                lines.push(format!(
+                    "      - {}",
+                    quote(&format!("{}:{}", mapping.host, mapping.container))
+                ));
+            }
+        }
+        if !spec.env.is_empty() {
+            lines.push("    environment:".to_string());
+            for (key, value) in &spec.env {
+                lines.push(format!("      {}: {}", quote(key), quote(value)));
+            }
+        }
+        if !spec.command.is_empty() {
+            lines.push(format!("    command: {}", flow_sequence(&spec.command)));
+        }
+        lines.push("    networks:".to_string());
+        lines.push(format!("      - {}", quote(&options.network)));
+        if let Some(healthcheck) = &options.healthcheck {
+            lines.push("    healthcheck:".to_string());
+            lines.push(format!(
+                "      test: {}",
+                flow_sequence(&["CMD-SHELL", healthcheck.command.as_str()])
+            ));
+            lines.push(format!("      interval: {}", quote(&healthcheck.interval)));
+            lines.push(format!("      timeout: {}", quote(&healthcheck.timeout)));
+            lines.push(format!("      retries: {}", healthcheck.retries));
+        }
+    }
+    lines.push("networks:".to_string());
+    lines.push(format!("  {}: {{}}", quote(&options.network)));
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod compose_tests {
+    use crate::compose::{compose, ComposeOptions, Healthcheck};
+    use crate::instance::WildFlyInstance;
+    use crate::ports::VersionBased;
+
+    #[test]
+    fn single_service() {
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        let yaml = compose(
+            &instances,
+            &VersionBased::default(),
+            &ComposeOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            r#"services:
+  "wildfly-26.1":
+    image: "quay.io/wildfly/wildfly:26.1.3.Final-jdk17"
+    container_name: "wildfly-26.1"
+    ports:
+      - "8261:8080"
+      - "9261:9990"
+    command: ["/opt/jboss/wildfly/bin/standalone.sh", "-b", "0.0.0.0", "-bmanagement", "0.0.0.0"]
+    networks:
+      - "wildfly"
+networks:
+  "wildfly": {}
+"#,
+            yaml
+        );
+    }
+
+    #[test]
+    fn unique_services() {
+        let instances = WildFlyInstance::enumeration("3x26.1,30..39").unwrap();
+        let yaml = compose(
+            &instances,
+            &VersionBased::default(),
+            &ComposeOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(13, yaml.matches("    image: ").count());
+        assert!(yaml.contains("  \"wildfly-26.1\":\n"));
+        assert!(yaml.contains("  \"wildfly-26.1-1\":\n"));
+        assert!(yaml.contains("  \"wildfly-26.1-2\":\n"));
+        assert!(yaml.contains("      - \"20261:8080\"\n"));
+    }
+
+    #[test]
+    fn options() {
+        let instances = WildFlyInstance::enumeration("39").unwrap();
+        let options = ComposeOptions {
+            project: Some("matrix".to_string()),
+            network: "test".to_string(),
+            healthcheck: Some(Healthcheck::default()),
+        };
+        let yaml = compose(&instances, &VersionBased::default(), &options).unwrap();
+        assert!(yaml.starts_with("name: \"matrix\"\nservices:\n"));
+        assert!(yaml.contains(
+            r#"    healthcheck:
+      test: ["CMD-SHELL", "curl -sf -o /dev/null http://localhost:8080/ || exit 1"]
+      interval: "10s"
+      timeout: "5s"
+      retries: 30
+"#
+        ));
+        assert!(yaml.ends_with("networks:\n  \"test\": {}\n"));
+    }
+}
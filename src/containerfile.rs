@@ -0,0 +1,202 @@
+//! Generates Containerfiles which customize the stock image of a [WildFlyContainer].
+//!
+//! A [Customization] declares what to layer on top of the image: a management user,
+//! environment variables, extra modules, CLI scripts and deployments. Both the
+//! `docker.io/jboss/wildfly` and the `quay.io/wildfly/wildfly` images install WildFly
+//! to [WILDFLY_HOME] and run as user `jboss`, so the same instructions work for all
+//! versions:
+//!
+//! ```text
+//! FROM quay.io/wildfly/wildfly:26.1.3.Final-jdk17
+//! RUN ["/opt/jboss/wildfly/bin/add-user.sh", "-u", "admin", "-p", "admin", "--silent"]
+//! COPY --chown=jboss:jboss ["app.war", "/opt/jboss/wildfly/standalone/deployments/app.war"]
+//! ```
+//!
+//! CLI scripts run as a batch against an embedded server at build time, so they must
+//! not contain `embed-server` or `batch` commands themselves.
+
+use crate::mirror;
+use crate::run::WILDFLY_HOME;
+use crate::yaml::{flow_sequence, quote};
+use crate::WildFlyContainer;
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// The owner of all copied files
+const OWNER: &str = "jboss:jboss";
+
+/// A user added to the management realm
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ManagementUser {
+    pub username: String,
+    pub password: String,
+}
+
+impl Default for ManagementUser {
+    fn default() -> Self {
+        Self {
+            username: "admin".to_string(),
+            password: "admin".to_string(),
+        }
+    }
+}
+
+/// What to add to the stock image. Paths are relative to the build context.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Customization {
+    /// An optional management user
+    pub management_user: Option<ManagementUser>,
+
+    /// Environment variables
+    pub env: Vec<(String, String)>,
+
+    /// Directories copied to `modules/` (e.g. "modules" containing "org/postgresql/main")
+    pub modules: Vec<String>,
+
+    /// CLI scripts run in order against an embedded server
+    pub cli_scripts: Vec<String>,
+
+    /// Deployments copied to `standalone/deployments/`
+    pub deployments: Vec<String>,
+}
+
+/// Renders a Containerfile which uses [WildFlyContainer::image_name] as base image
+/// and applies the customization.
+///
+/// Fails for dev builds and local distributions, which have no image,
+/// and for paths without a file name.
+pub fn containerfile(wildfly: &WildFlyContainer, customization: &Customization) -> Result<String> {
+    if !wildfly.has_image() {
+        bail!("no image for {}", wildfly.image_name())
+    }
+    mirror::validate()?;
+    let mut lines = vec![format!("FROM {}", wildfly.image_name())];
+    for (key, value) in &customization.env {
+        lines.push(format!("ENV {}={}", key, quote_value(value)));
+    }
+    if let Some(user) = &customization.management_user {
+        lines.push(format!(
+            "RUN {}",
+            flow_sequence(&[
+                format!("{}/bin/add-user.sh", WILDFLY_HOME).as_str(),
+                "-u",
+                user.username.as_str(),
+                "-p",
+                user.password.as_str(),
+                "--silent",
+            ])
+        ));
+    }
+    for module in &customization.modules {
+        lines.push(copy(module, &format!("{}/modules/", WILDFLY_HOME)));
+    }
+    if !customization.cli_scripts.is_empty() {
+        let mut commands = vec!["embed-server --std-out=discard".to_string()];
+        for script in &customization.cli_scripts {
+            let target = format!("/tmp/cli/{}", file_name(script)?);
+            lines.push(copy(script, &target));
+            commands.push(format!("run-batch --file={}", target));
+        }
+        commands.push("stop-embedded-server".to_string());
+        lines.push(format!(
+            "RUN {}",
+            flow_sequence(&[
+                format!("{}/bin/jboss-cli.sh", WILDFLY_HOME),
+                format!("--commands={}", commands.join(",")),
+            ])
+        ));
+        // the embedded server leaves its configuration history behind
+        lines.push(format!(
+            "RUN rm -rf /tmp/cli {}/standalone/configuration/standalone_xml_history/current",
+            WILDFLY_HOME
+        ));
+    }
+    for deployment in &customization.deployments {
+        let target = format!(
+            "{}/standalone/deployments/{}",
+            WILDFLY_HOME,
+            file_name(deployment)?
+        );
+        lines.push(copy(deployment, &target));
+    }
+    lines.push(String::new());
+    Ok(lines.join("\n"))
+}
+
+fn copy(source: &str, target: &str) -> String {
+    format!(
+        "COPY --chown={} {}",
+        OWNER,
+        flow_sequence(&[source, target])
+    )
+}
+
+fn file_name(path: &str) -> Result<&str> {
+    match Path::new(path).file_name().and_then(|name| name.to_str()) {
+        Some(name) => Ok(name),
+        None => bail!("no file name in '{}'", path),
+    }
+}
+
+/// Quotes an `ENV` or `LABEL` value and prevents variable substitution.
+pub(crate) fn quote_value(value: &str) -> String {
+    quote(value).replace('$', "\\$")
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod containerfile_tests {
+    use crate::containerfile::{containerfile, Customization, ManagementUser};
+    use crate::WildFlyContainer;
+
+    #[test]
+    fn stock() {
+        let wildfly = WildFlyContainer::version("26.1").unwrap();
+        assert_eq!(
+            "FROM quay.io/wildfly/wildfly:26.1.3.Final-jdk17\n",
+            containerfile(&wildfly, &Customization::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn customized() {
+        let wildfly = WildFlyContainer::version("20").unwrap();
+        let customization = Customization {
+            management_user: Some(ManagementUser::default()),
+            env: vec![("JAVA_OPTS".to_string(), "-Xmx1g -Dhome=$HOME".to_string())],
+            modules: vec!["modules".to_string()],
+            cli_scripts: vec![
+                "cli/datasource.cli".to_string(),
+                "cli/logging.cli".to_string(),
+            ],
+            deployments: vec!["target/app.war".to_string()],
+        };
+        assert_eq!(
+            r#"FROM docker.io/jboss/wildfly:20.0.1.Final
+ENV JAVA_OPTS="-Xmx1g -Dhome=\$HOME"
+RUN ["/opt/jboss/wildfly/bin/add-user.sh", "-u", "admin", "-p", "admin", "--silent"]
+COPY --chown=jboss:jboss ["modules", "/opt/jboss/wildfly/modules/"]
+COPY --chown=jboss:jboss ["cli/datasource.cli", "/tmp/cli/datasource.cli"]
+COPY --chown=jboss:jboss ["cli/logging.cli", "/tmp/cli/logging.cli"]
+RUN ["/opt/jboss/wildfly/bin/jboss-cli.sh", "--commands=embed-server --std-out=discard,run-batch --file=/tmp/cli/datasource.cli,run-batch --file=/tmp/cli/logging.cli,stop-embedded-server"]
+RUN rm -rf /tmp/cli /opt/jboss/wildfly/standalone/configuration/standalone_xml_history/current
+COPY --chown=jboss:jboss ["target/app.war", "/opt/jboss/wildfly/standalone/deployments/app.war"]
+"#,
+            containerfile(&wildfly, &customization).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let dev = WildFlyContainer::version("dev").unwrap();
+        assert!(containerfile(&dev, &Customization::default()).is_err());
+
+        let wildfly = WildFlyContainer::version("39").unwrap();
+        let customization = Customization {
+            deployments: vec!["..".to_string()],
+            ..Customization::default()
+        };
+        assert!(containerfile(&wildfly, &customization).is_err());
+    }
+}
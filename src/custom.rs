@@ -0,0 +1,137 @@
+//! Custom images like patched or rebuilt WildFly images.
+//!
+//! The enumeration DSL accepts `image:<reference>` segments. The WildFly version is inferred
+//! from the tag ("registry.local/team/wildfly:26.1.3-patched" → 26.1) or given explicitly
+//! after `=` ("image:registry.local/team/wildfly:latest=26.1"). The version must be part of
+//! the catalog: A custom image uses the metadata, ports and ordering of its catalog version,
+//! but [WildFlyContainer::image_name] returns the custom reference. The digests of the catalog
+//! version don't apply to the custom image.
+
+use crate::WildFlyContainer;
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// The prefix of custom images in the enumeration DSL
+pub const IMAGE_PREFIX: &str = "image:";
+
+lazy_static! {
+    static ref TAG_VERSION_RE: Regex =
+        Regex::new(r"^v?(?<major>[0-9]+)(\.(?<minor>[0-9]+))?([.\-_+]|$)").unwrap();
+}
+
+/// Creates a [WildFlyContainer] for a custom image. Without a version, the version is
+/// inferred from the tag of the reference.
+pub fn custom_image(reference: &str, version: Option<&str>) -> Result<WildFlyContainer> {
+    if reference.is_empty() || reference.contains(char::is_whitespace) {
+        bail!("invalid image reference '{}'", reference)
+    }
+    let short_version = match version {
+        Some(version) => version.to_string(),
+        None => infer_version(reference)?,
+    };
+    let wildfly = WildFlyContainer::version(&short_version)?;
+    if !wildfly.has_image() {
+        bail!("custom image {} needs a released version", reference)
+    }
+    Ok(WildFlyContainer {
+        custom_image: Some(reference.to_string()),
+        digests: Default::default(),
+        ..wildfly
+    })
+}
+
+/// Parses the DSL of a custom image like "image:registry.local/team/wildfly:26.1.3-patched"
+/// or "image:registry.local/team/wildfly:latest=26.1".
+pub fn parse(image: &str) -> Result<WildFlyContainer> {
+    let Some(image) = image.strip_prefix(IMAGE_PREFIX) else {
+        bail!("invalid custom image '{}'", image)
+    };
+    match image.split_once('=') {
+        Some((reference, version)) => custom_image(reference, Some(version)),
+        None => custom_image(image, None),
+    }
+}
+
+fn infer_version(reference: &str) -> Result<String> {
+    // the tag follows the last colon after the last slash (the registry may have a port)
+    let name = reference.split('@').next().unwrap_or(reference);
+    let last_segment = name.rsplit('/').next().unwrap_or(name);
+    let Some((_, tag)) = last_segment.split_once(':') else {
+        bail!(
+            "no tag in image {} to infer the version from, use {}{}=<version>",
+            reference,
+            IMAGE_PREFIX,
+            reference
+        )
+    };
+    match TAG_VERSION_RE.captures(tag) {
+        Some(c) => Ok(match c.name("minor") {
+            Some(minor) => format!("{}.{}", &c["major"], minor.as_str()),
+            None => c["major"].to_string(),
+        }),
+        None => bail!(
+            "unable to infer the version from tag {}, use {}{}=<version>",
+            tag,
+            IMAGE_PREFIX,
+            reference
+        ),
+    }
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod custom_tests {
+    use crate::custom::{custom_image, parse};
+    use crate::WildFlyContainer;
+
+    #[test]
+    fn inferred() {
+        let wildfly = parse("image:registry.local/team/wildfly:26.1.3-patched").unwrap();
+        assert_eq!(261, wildfly.identifier);
+        assert_eq!(
+            "registry.local/team/wildfly:26.1.3-patched",
+            wildfly.image_name()
+        );
+        assert_eq!(8261, wildfly.http_port().unwrap());
+
+        let wildfly = parse("image:registry.local:5000/wildfly:v34").unwrap();
+        assert_eq!(340, wildfly.identifier);
+    }
+
+    #[test]
+    fn explicit() {
+        let wildfly = parse("image:registry.local/team/wildfly:latest=26.1").unwrap();
+        assert_eq!(261, wildfly.identifier);
+        assert_eq!("registry.local/team/wildfly:latest", wildfly.image_name());
+        let wildfly = custom_image("localhost/wildfly", Some("39")).unwrap();
+        assert_eq!("localhost/wildfly", wildfly.image_name());
+        assert!(wildfly.digests.is_empty());
+        assert!(wildfly.pinned_image_name("linux/amd64").is_err());
+    }
+
+    #[test]
+    fn enumeration() {
+        let result = WildFlyContainer::enumeration(
+            "39,image:registry.local/wildfly:26.1-fix,26.1,2ximage:localhost/wildfly:latest=10",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![100, 100, 261, 261, 390],
+            result.iter().map(|w| w.identifier).collect::<Vec<_>>()
+        );
+        assert_eq!("localhost/wildfly:latest", result[0].image_name());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(parse("image:").is_err());
+        assert!(parse("image:registry.local/team/wildfly").is_err());
+        assert!(parse("image:registry.local/team/wildfly:latest").is_err());
+        assert!(parse("image:registry.local:5000/wildfly").is_err());
+        assert!(parse("image:registry.local/wildfly:1.2").is_err());
+        assert!(parse("image:registry.local/wildfly:latest=dev").is_err());
+        assert!(parse("image:registry.local/wildfly:latest=foo").is_err());
+    }
+}
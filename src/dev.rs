@@ -0,0 +1,201 @@
+//! Describes development builds, which are built from source instead of pulled from a registry.
+//!
+//! The enumeration DSL selects the sources of a development build:
+//!
+//! | DSL                  | Repository                               | Reference               |
+//! |----------------------|------------------------------------------|-------------------------|
+//! | `dev`                | `https://github.com/wildfly/wildfly.git` | branch `main`           |
+//! | `dev@main`           | `https://github.com/wildfly/wildfly.git` | branch `main`           |
+//! | `dev@31.0.0.Final`   | `https://github.com/wildfly/wildfly.git` | tag `31.0.0.Final`      |
+//! | `dev#12345`          | `https://github.com/wildfly/wildfly.git` | pull request 12345      |
+//! | `dev@myfork/branch`  | `https://github.com/myfork/wildfly.git`  | branch `branch`         |
+//!
+//! In `dev@<owner>/<branch>`, the first segment always names the owner of the fork,
+//! so branches with slashes like `dev@myfork/feature/foo` work as well.
+//! Tags of WildFly releases set the expected version of the build.
+
+use crate::DEVELOPMENT_VERSION;
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::Version;
+
+/// The repository of the upstream WildFly sources
+pub const WILDFLY_REPOSITORY: &str = "https://github.com/wildfly/wildfly.git";
+
+/// The branch used if no reference is given
+pub const DEFAULT_BRANCH: &str = "main";
+
+lazy_static! {
+    static ref TAG_RE: Regex = Regex::new(
+        r"^(?<major>[0-9]+)\.(?<minor>[0-9]+)\.(?<patch>[0-9]+)\.[A-Za-z][A-Za-z0-9-]*$"
+    )
+    .unwrap();
+    static ref OWNER_RE: Regex = Regex::new(r"^[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?$").unwrap();
+}
+
+/// The git reference of a development build
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum GitRef {
+    Branch(String),
+    Tag(String),
+    PullRequest(u32),
+}
+
+impl GitRef {
+    /// Returns the name to fetch: the branch or tag name, or `pull/<number>/head`.
+    pub fn fetch_name(&self) -> String {
+        match self {
+            GitRef::Branch(name) | GitRef::Tag(name) => name.clone(),
+            GitRef::PullRequest(number) => format!("pull/{}/head", number),
+        }
+    }
+}
+
+/// The sources of a development build
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct DevBuild {
+    /// The git repository URL
+    pub repository: String,
+
+    /// The branch, tag or pull request
+    pub reference: GitRef,
+
+    /// The WildFly version the build is expected to produce, if known
+    pub expected_version: Option<Version>,
+}
+
+impl DevBuild {
+    /// Creates a development build of an arbitrary repository.
+    /// The expected version is derived from release tags like "31.0.0.Final".
+    pub fn new(repository: &str, reference: GitRef) -> Self {
+        let expected_version = match &reference {
+            GitRef::Tag(tag) => TAG_RE.captures(tag).and_then(|c| {
+                Some(Version::new(
+                    c["major"].parse().ok()?,
+                    c["minor"].parse().ok()?,
+                    c["patch"].parse().ok()?,
+                ))
+            }),
+            _ => None,
+        };
+        Self {
+            repository: repository.to_string(),
+            reference,
+            expected_version,
+        }
+    }
+
+    /// Parses the DSL of a development build like "dev", "dev@main", "dev@31.0.0.Final",
+    /// "dev#12345" or "dev@myfork/branch".
+    pub fn parse(dev: &str) -> Result<DevBuild> {
+        let Some(selector) = dev.strip_prefix(DEVELOPMENT_VERSION) else {
+            bail!("invalid development build '{}'", dev)
+        };
+        if selector.is_empty() {
+            Ok(DevBuild::default())
+        } else if let Some(number) = selector.strip_prefix('#') {
+            match number.parse::<u32>() {
+                Ok(number) if number > 0 => Ok(DevBuild::new(
+                    WILDFLY_REPOSITORY,
+                    GitRef::PullRequest(number),
+                )),
+                _ => bail!("invalid pull request in '{}'", dev),
+            }
+        } else if let Some(reference) = selector.strip_prefix('@') {
+            if let Some((owner, branch)) = reference.split_once('/') {
+                if !OWNER_RE.is_match(owner) || !valid_ref(branch) {
+                    bail!("invalid fork or branch in '{}'", dev)
+                }
+                Ok(DevBuild::new(
+                    &format!("https://github.com/{}/wildfly.git", owner),
+                    GitRef::Branch(branch.to_string()),
+                ))
+            } else if TAG_RE.is_match(reference) {
+                Ok(DevBuild::new(
+                    WILDFLY_REPOSITORY,
+                    GitRef::Tag(reference.to_string()),
+                ))
+            } else if valid_ref(reference) {
+                Ok(DevBuild::new(
+                    WILDFLY_REPOSITORY,
+                    GitRef::Branch(reference.to_string()),
+                ))
+            } else {
+                bail!("invalid reference in '{}'", dev)
+            }
+        } else {
+            bail!("invalid development build '{}'", dev)
+        }
+    }
+}
+
+impl Default for DevBuild {
+    /// Builds the main branch of the upstream repository.
+    fn default() -> Self {
+        Self::new(
+            WILDFLY_REPOSITORY,
+            GitRef::Branch(DEFAULT_BRANCH.to_string()),
+        )
+    }
+}
+
+/// Rejects references which git doesn't accept or which clash with the DSL.
+fn valid_ref(reference: &str) -> bool {
+    !reference.is_empty()
+        && !reference.starts_with(['-', '/'])
+        && !reference.ends_with(['/', '.'])
+        && !reference.contains("..")
+        && !reference.contains("//")
+        && !reference
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || "~^:?*[\\,@#".contains(c))
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod dev_tests {
+    use crate::dev::{DevBuild, GitRef, WILDFLY_REPOSITORY};
+    use semver::Version;
+
+    #[test]
+    fn parse_ok() {
+        assert_eq!(DevBuild::default(), DevBuild::parse("dev").unwrap());
+        assert_eq!(DevBuild::default(), DevBuild::parse("dev@main").unwrap());
+
+        let build = DevBuild::parse("dev@31.0.0.Final").unwrap();
+        assert_eq!(WILDFLY_REPOSITORY, build.repository);
+        assert_eq!(GitRef::Tag("31.0.0.Final".to_string()), build.reference);
+        assert_eq!(Some(Version::new(31, 0, 0)), build.expected_version);
+
+        let build = DevBuild::parse("dev#12345").unwrap();
+        assert_eq!(GitRef::PullRequest(12345), build.reference);
+        assert_eq!("pull/12345/head", build.reference.fetch_name());
+        assert_eq!(None, build.expected_version);
+
+        let build = DevBuild::parse("dev@myfork/feature/WFLY-123-fix").unwrap();
+        assert_eq!("https://github.com/myfork/wildfly.git", build.repository);
+        assert_eq!(
+            GitRef::Branch("feature/WFLY-123-fix".to_string()),
+            build.reference
+        );
+    }
+
+    #[test]
+    fn parse_err() {
+        assert!(DevBuild::parse("").is_err());
+        assert!(DevBuild::parse("foo").is_err());
+        assert!(DevBuild::parse("development").is_err());
+        assert!(DevBuild::parse("dev@").is_err());
+        assert!(DevBuild::parse("dev#").is_err());
+        assert!(DevBuild::parse("dev#0").is_err());
+        assert!(DevBuild::parse("dev#abc").is_err());
+        assert!(DevBuild::parse("dev@a b").is_err());
+        assert!(DevBuild::parse("dev@-main").is_err());
+        assert!(DevBuild::parse("dev@/main").is_err());
+        assert!(DevBuild::parse("dev@fork/").is_err());
+        assert!(DevBuild::parse("dev@-fork/main").is_err());
+        assert!(DevBuild::parse("dev@main@x").is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! Per-platform digests of the catalog images.
+//!
+//! Tags are mutable, digests are not. The catalog entries carry the digests of their platform
+//! manifests ([WildFlyContainer::digests]), which the [VERSIONS](crate::VERSIONS) initializer
+//! adds from the table in `src/digests.txt`. A table contains one digest per line:
+//!
+//! ```text
+//! # image platform digest
+//! quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21 linux/amd64 sha256:…
+//! quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21 linux/arm64 sha256:…
+//! ```
+//!
+//! [fetch_digests](crate::verify::fetch_digests) reads such a table from the registries
+//! (`Digests::to_string`). Other tables are loaded with [Digests::load] or from the file named
+//! by the environment variable `WILDFLY_DIGESTS` ([Digests::from_env]) and added to containers
+//! with [Digests::apply].
+
+use crate::image::ImageReference;
+use crate::WildFlyContainer;
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::{env, fs};
+
+/// The environment variable with the path of a digest file
+pub const DIGESTS_ENV: &str = "WILDFLY_DIGESTS";
+
+/// The digests of the catalog images
+const CATALOG_DIGESTS: &str = include_str!("digests.txt");
+
+/// The digests of images by upstream image name and platform
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Digests {
+    images: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Digests {
+    /// Adds the digest of an image like "quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21"
+    /// for a platform like "linux/amd64".
+    pub fn with(mut self, image: &str, platform: &str, digest: &str) -> Self {
+        self.images
+            .entry(image.to_string())
+            .or_default()
+            .insert(platform.to_string(), digest.to_string());
+        self
+    }
+
+    /// Parses lines like "quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21 linux/amd64 sha256:…".
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn parse(content: &str) -> Result<Digests> {
+        let mut digests = Digests::default();
+        for line in content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [image, platform, digest] = fields[..] else {
+                bail!("invalid digest line '{}'", line)
+            };
+            ImageReference::parse(&format!("{}@{}", image, digest))
+                .map_err(|e| anyhow!("invalid digest line '{}': {}", line, e))?;
+            digests = digests.with(image, platform, digest);
+        }
+        Ok(digests)
+    }
+
+    /// Reads the digests from a file.
+    pub fn load(path: &Path) -> Result<Digests> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("unable to read {}: {}", path.display(), e))?;
+        Self::parse(&content).map_err(|e| anyhow!("invalid digest file {}: {}", path.display(), e))
+    }
+
+    /// Reads the digests from the file named by the environment variable [DIGESTS_ENV].
+    /// Returns an empty table if the variable is not set.
+    ///
+    /// Fails if the file can't be read or is invalid.
+    pub fn from_env() -> Result<Digests> {
+        match env::var(DIGESTS_ENV) {
+            Ok(path) => Self::load(Path::new(&path)),
+            Err(_) => Ok(Digests::default()),
+        }
+    }
+
+    /// Returns the digests of the image by platform.
+    pub fn get(&self, image: &str) -> Option<&BTreeMap<String, String>> {
+        self.images.get(image)
+    }
+
+    /// Returns the images with digests.
+    pub fn images(&self) -> impl Iterator<Item = &str> {
+        self.images.keys().map(String::as_str)
+    }
+
+    /// Returns `true` if there are no digests.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Adds the digests of [WildFlyContainer::upstream_image_name] to the container.
+    /// Containers without an image are returned unchanged.
+    pub fn apply(&self, wildfly: WildFlyContainer) -> WildFlyContainer {
+        if !wildfly.has_image() {
+            return wildfly;
+        }
+        match self.get(&wildfly.upstream_image_name()) {
+            Some(platforms) => platforms
+                .iter()
+                .fold(wildfly, |wildfly, (platform, digest)| {
+                    wildfly.with_digest(platform, digest)
+                }),
+            None => wildfly,
+        }
+    }
+}
+
+impl Display for Digests {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (image, platforms) in &self.images {
+            for (platform, digest) in platforms {
+                writeln!(f, "{} {} {}", image, platform, digest)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the digests of the catalog images.
+pub(crate) fn catalog() -> Digests {
+    Digests::parse(CATALOG_DIGESTS).expect("invalid src/digests.txt")
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod digests_tests {
+    use crate::digests::{catalog, Digests};
+    use crate::{WildFlyContainer, VERSIONS};
+    use std::{env, fs};
+
+    const AMD64: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    const ARM64: &str = "sha256:fedcba9876543210fedcba9876543210fedcba9876543210fedcba9876543210";
+
+    #[test]
+    fn parse() {
+        let content = format!(
+            "# image platform digest\n\nquay.io/wildfly/wildfly:36.0.1.Final-jdk21 linux/amd64 {}\nquay.io/wildfly/wildfly:36.0.1.Final-jdk21 linux/arm64 {}\n",
+            AMD64, ARM64
+        );
+        let digests = Digests::parse(&content).unwrap();
+        let platforms = digests
+            .get("quay.io/wildfly/wildfly:36.0.1.Final-jdk21")
+            .unwrap();
+        assert_eq!(AMD64, platforms["linux/amd64"]);
+        assert_eq!(ARM64, platforms["linux/arm64"]);
+        assert_eq!(
+            content.lines().skip(2).collect::<Vec<_>>(),
+            digests.to_string().lines().collect::<Vec<_>>()
+        );
+
+        assert!(Digests::parse("").unwrap().is_empty());
+        assert!(Digests::parse("quay.io/wildfly/wildfly:36.0.1.Final-jdk21 linux/amd64").is_err());
+        assert!(Digests::parse(
+            "quay.io/wildfly/wildfly:36.0.1.Final-jdk21 linux/amd64 sha256:abc"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn load_invalid() {
+        let path = env::temp_dir().join(format!("wildfly-digests-{}.txt", std::process::id()));
+        fs::write(
+            &path,
+            "quay.io/wildfly/wildfly:36.0.1.Final-jdk21 linux/amd64\n",
+        )
+        .unwrap();
+        let result = Digests::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid digest file"));
+        assert!(Digests::load(&env::temp_dir().join("wildfly-digests-missing.txt")).is_err());
+    }
+
+    #[test]
+    fn catalog_digests() {
+        let digests = catalog();
+        for image in digests.images() {
+            let wildfly = VERSIONS
+                .values()
+                .find(|w| w.upstream_image_name() == image)
+                .unwrap_or_else(|| panic!("{} is not a catalog image", image));
+            assert_eq!(digests.get(image), Some(&wildfly.digests));
+        }
+    }
+
+    #[test]
+    fn apply() {
+        let image = "quay.io/wildfly/wildfly:37.0.1.Final-jdk21";
+        let digests =
+            Digests::default()
+                .with(image, "linux/amd64", AMD64)
+                .with(image, "linux/arm64", ARM64);
+        let wildfly = digests.apply(WildFlyContainer::version("37").unwrap());
+        assert_eq!(2, wildfly.digests.len());
+        assert_eq!(
+            format!("{}@{}", image, ARM64),
+            wildfly.pinned_image_name("linux/arm64").unwrap()
+        );
+        assert!(wildfly.pinned_image_name("linux/s390x").is_err());
+
+        let other = digests.apply(WildFlyContainer::version("36").unwrap());
+        assert!(other.digests.is_empty());
+        let dev = digests.apply(WildFlyContainer::version("dev").unwrap());
+        assert!(dev.digests.is_empty());
+    }
+}
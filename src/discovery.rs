@@ -0,0 +1,274 @@
+//! Discovers running WildFly instances by probing their management ports.
+//!
+//! [discover] computes the management port of each catalog version, connects to it
+//! and reads the product name and version from the management HTTP endpoint
+//! (`GET /management`). Ports which refuse the connection are skipped.
+
+use crate::instance::WildFlyInstance;
+use crate::ports::{PortStrategy, VersionBased};
+use crate::VERSIONS;
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::Version;
+use serde_json::Value;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+lazy_static! {
+    static ref PRODUCT_VERSION_RE: Regex =
+        Regex::new(r"^(?<major>[0-9]+)\.(?<minor>[0-9]+)\.(?<patch>[0-9]+)").unwrap();
+}
+
+/// What the management endpoint answered
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Probe {
+    /// The management endpoint returned the product information.
+    Product {
+        /// The product name like "WildFly Full" or "WildFly"
+        name: String,
+
+        /// The product version like "26.1.3.Final"
+        version: String,
+    },
+
+    /// The management endpoint requires authentication.
+    Unauthorized,
+
+    /// Something listens on the port, but didn't answer like a WildFly management endpoint.
+    Unexpected(String),
+}
+
+/// A running instance found by [discover]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Discovery {
+    /// The instance expected at the probed port
+    pub instance: WildFlyInstance,
+
+    /// The probed management port
+    pub port: u16,
+
+    /// What the management endpoint answered
+    pub probe: Probe,
+}
+
+impl Discovery {
+    /// Returns the major, minor and patch version reported by the management endpoint.
+    pub fn reported_version(&self) -> Option<Version> {
+        match &self.probe {
+            Probe::Product { version, .. } => parse_product_version(version),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the reported version is the version of the catalog entry.
+    pub fn matches(&self) -> bool {
+        self.reported_version()
+            .is_some_and(|version| version == self.instance.wildfly.version)
+    }
+}
+
+/// Probes the management ports of all catalog versions on the given host.
+///
+/// The ports are computed with the default [VersionBased] strategy.
+/// `timeout` applies to each connection and request.
+pub fn discover(host: &str, timeout: Duration) -> Vec<Discovery> {
+    let instances = VERSIONS
+        .values()
+        .map(|wildfly| WildFlyInstance::new(wildfly.clone(), 0))
+        .collect::<Vec<_>>();
+    discover_instances(host, &instances, &VersionBased::default(), timeout)
+}
+
+/// Probes the management ports of the given instances on the given host.
+///
+/// The ports are computed with the given [PortStrategy]. The probes run in parallel.
+/// The result contains only instances whose management port accepts connections.
+pub fn discover_instances(
+    host: &str,
+    instances: &[WildFlyInstance],
+    strategy: &dyn PortStrategy,
+    timeout: Duration,
+) -> Vec<Discovery> {
+    let candidates = instances
+        .iter()
+        .filter_map(|instance| {
+            strategy
+                .instance_ports(instance)
+                .ok()
+                .map(|ports| (instance, ports.management))
+        })
+        .collect::<Vec<_>>();
+    thread::scope(|scope| {
+        let handles = candidates
+            .into_iter()
+            .map(|(instance, port)| {
+                scope.spawn(move || {
+                    probe(host, port, timeout).map(|probe| Discovery {
+                        instance: instance.clone(),
+                        port,
+                        probe,
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok().flatten())
+            .collect()
+    })
+}
+
+/// Probes a single management port.
+///
+/// Returns `None` if the port doesn't accept connections.
+pub fn probe(host: &str, port: u16, timeout: Duration) -> Option<Probe> {
+    let address = (host, port).to_socket_addrs().ok()?.next()?;
+    TcpStream::connect_timeout(&address, timeout).ok()?;
+
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    // the socket address brackets IPv6 literals like ::1
+    let url = format!("http://{}/management", address);
+    let result = match agent.get(&url).call() {
+        Ok(response) => response
+            .into_string()
+            .map_err(anyhow::Error::from)
+            .and_then(|body| product(&body)),
+        Err(ureq::Error::Status(401, _)) => return Some(Probe::Unauthorized),
+        Err(e) => Err(e.into()),
+    };
+    Some(result.unwrap_or_else(|e| Probe::Unexpected(e.to_string())))
+}
+
+fn product(body: &str) -> Result<Probe> {
+    let json: Value = serde_json::from_str(body)?;
+    // POST requests wrap the resource in "result", GET requests don't
+    let resource = json.get("result").unwrap_or(&json);
+    let attribute = |name: &str| {
+        resource
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("no {} in management response", name))
+    };
+    Ok(Probe::Product {
+        name: attribute("product-name")?,
+        version: attribute("product-version")?,
+    })
+}
+
+fn parse_product_version(version: &str) -> Option<Version> {
+    let c = PRODUCT_VERSION_RE.captures(version)?;
+    Some(Version::new(
+        c["major"].parse().ok()?,
+        c["minor"].parse().ok()?,
+        c["patch"].parse().ok()?,
+    ))
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod discovery_tests {
+    use crate::discovery::{discover_instances, probe, Probe};
+    use crate::instance::WildFlyInstance;
+    use crate::ports::{Ephemeral, PortStrategy, Ports};
+    use crate::testing::{closed_port, port, serve, stub, Fixed, TIMEOUT};
+    use crate::WildFlyContainer;
+    use std::net::{Ipv6Addr, TcpListener};
+
+    /// Starts a stub server which answers all requests with the given status and body.
+    fn management(status: &'static str, body: &'static str) -> u16 {
+        port(&stub(move |_| (status, vec![], body.to_string())))
+    }
+
+    #[test]
+    fn probe_product() {
+        let port = management(
+            "200 OK",
+            r#"{"product-name":"WildFly Full","product-version":"26.1.3.Final","release-version":"18.1.2.Final"}"#,
+        );
+        assert_eq!(
+            Some(Probe::Product {
+                name: "WildFly Full".to_string(),
+                version: "26.1.3.Final".to_string()
+            }),
+            probe("localhost", port, TIMEOUT)
+        );
+    }
+
+    #[test]
+    fn probe_unauthorized() {
+        let port = management("401 Unauthorized", "");
+        assert_eq!(Some(Probe::Unauthorized), probe("localhost", port, TIMEOUT));
+    }
+
+    #[test]
+    fn probe_unexpected() {
+        let port = management("200 OK", "<html></html>");
+        assert!(matches!(
+            probe("localhost", port, TIMEOUT),
+            Some(Probe::Unexpected(_))
+        ));
+    }
+
+    #[test]
+    fn probe_ipv6() {
+        // skip hosts without IPv6
+        let Ok(listener) = TcpListener::bind((Ipv6Addr::LOCALHOST, 0)) else {
+            return;
+        };
+        let port = listener.local_addr().unwrap().port();
+        serve(listener, |_| {
+            (
+                "200 OK",
+                vec![],
+                r#"{"product-name":"WildFly Full","product-version":"39.0.1.Final"}"#.to_string(),
+            )
+        });
+        assert!(matches!(
+            probe("::1", port, TIMEOUT),
+            Some(Probe::Product { .. })
+        ));
+    }
+
+    #[test]
+    fn probe_closed() {
+        assert_eq!(None, probe("localhost", closed_port(), TIMEOUT));
+    }
+
+    #[test]
+    fn discover_matching_version() {
+        let port = management(
+            "200 OK",
+            r#"{"outcome":"success","result":{"product-name":"WildFly Full","product-version":"26.1.3.Final"}}"#,
+        );
+        let wf = WildFlyContainer::version("26.1").unwrap();
+        let strategy = Fixed(Ports {
+            management: port,
+            ..Ephemeral.ports(&wf).unwrap()
+        });
+        let instances = WildFlyInstance::enumeration("26.1,27").unwrap();
+        let discovered = discover_instances("localhost", &instances, &strategy, TIMEOUT);
+        assert_eq!(2, discovered.len());
+        assert_eq!(port, discovered[0].port);
+        assert!(discovered[0].matches());
+        assert!(!discovered[1].matches());
+        assert_eq!(
+            Some(semver::Version::new(26, 1, 3)),
+            discovered[1].reported_version()
+        );
+    }
+
+    #[test]
+    fn discover_nothing() {
+        let wf = WildFlyContainer::version("26.1").unwrap();
+        let strategy = Fixed(Ports {
+            management: closed_port(),
+            ..Ephemeral.ports(&wf).unwrap()
+        });
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        assert!(discover_instances("localhost", &instances, &strategy, TIMEOUT).is_empty());
+    }
+}
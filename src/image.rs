@@ -0,0 +1,206 @@
+//! OCI image references like "quay.io/wildfly/wildfly:26.1.3.Final-jdk17@sha256:…".
+//!
+//! [ImageReference::parse] follows the rules of the container engines: The first path
+//! segment is the registry if it contains a dot or a colon or is "localhost", otherwise
+//! the image lives on Docker Hub ("jboss/wildfly" → "docker.io/jboss/wildfly").
+
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::{Display, Formatter};
+
+/// The registry of images without an explicit registry
+pub const DOCKER_HUB: &str = "docker.io";
+
+/// Images without platforms are only available for this platform.
+pub const DEFAULT_PLATFORM: &str = "linux/amd64";
+
+lazy_static! {
+    static ref DIGEST_RE: Regex =
+        Regex::new(r"^[a-z0-9]+([+._-][a-z0-9]+)*:[a-zA-Z0-9=_-]{32,}$").unwrap();
+    static ref TAG_RE: Regex = Regex::new(r"^[A-Za-z0-9_][A-Za-z0-9_.-]{0,127}$").unwrap();
+    static ref COMPONENT_RE: Regex = Regex::new(r"^[a-z0-9]+((\.|_|__|-+)[a-z0-9]+)*$").unwrap();
+}
+
+/// A parsed image reference
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ImageReference {
+    /// The registry like "quay.io" or "localhost:5000"
+    pub registry: String,
+
+    /// The path between registry and name like "wildfly" or "team/sub", may be empty
+    pub namespace: String,
+
+    /// The image name like "wildfly"
+    pub name: String,
+
+    /// The tag like "26.1.3.Final-jdk17"
+    pub tag: Option<String>,
+
+    /// The digest like "sha256:…"
+    pub digest: Option<String>,
+}
+
+impl ImageReference {
+    /// Parses an image reference.
+    pub fn parse(reference: &str) -> Result<ImageReference> {
+        let (name, digest) = match reference.split_once('@') {
+            Some((name, digest)) => {
+                if !DIGEST_RE.is_match(digest) {
+                    bail!("invalid digest in image {}", reference)
+                }
+                (name, Some(digest.to_string()))
+            }
+            None => (reference, None),
+        };
+        let (repository, tag) = match name.rsplit_once(':') {
+            Some((repository, tag)) if !tag.contains('/') => {
+                if !TAG_RE.is_match(tag) {
+                    bail!("invalid tag in image {}", reference)
+                }
+                (repository, Some(tag.to_string()))
+            }
+            _ => (name, None),
+        };
+        let mut segments = repository.split('/').collect::<Vec<_>>();
+        let registry = if segments.len() > 1
+            && (segments[0].contains(['.', ':']) || segments[0] == "localhost")
+        {
+            match segments.remove(0) {
+                "index.docker.io" | "registry-1.docker.io" => DOCKER_HUB.to_string(),
+                registry => registry.to_string(),
+            }
+        } else {
+            DOCKER_HUB.to_string()
+        };
+        if segments
+            .iter()
+            .any(|segment| !COMPONENT_RE.is_match(segment))
+        {
+            bail!("invalid repository in image {}", reference)
+        }
+        let name = segments.pop().unwrap_or_default().to_string();
+        let namespace = if segments.is_empty() && registry == DOCKER_HUB {
+            "library".to_string()
+        } else {
+            segments.join("/")
+        };
+        Ok(ImageReference {
+            registry,
+            namespace,
+            name,
+            tag,
+            digest,
+        })
+    }
+
+    /// Returns the repository without tag and digest like "quay.io/wildfly/wildfly".
+    pub fn repository(&self) -> String {
+        if self.namespace.is_empty() {
+            format!("{}/{}", self.registry, self.name)
+        } else {
+            format!("{}/{}/{}", self.registry, self.namespace, self.name)
+        }
+    }
+
+    /// Returns the path of the repository inside the registry like "wildfly/wildfly".
+    pub fn path(&self) -> String {
+        if self.namespace.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}/{}", self.namespace, self.name)
+        }
+    }
+
+    /// Returns a copy pinned to the given digest.
+    pub fn with_digest(&self, digest: &str) -> ImageReference {
+        ImageReference {
+            digest: Some(digest.to_string()),
+            ..self.clone()
+        }
+    }
+}
+
+impl Display for ImageReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.repository())?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{}", tag)?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if a platform advertised by a registry like "linux/arm64/v8" is the given
+/// platform like "linux/arm64", optionally with a variant.
+pub fn matches_platform(advertised: &str, platform: &str) -> bool {
+    advertised == platform
+        || advertised
+            .strip_prefix(platform)
+            .is_some_and(|variant| variant.starts_with('/'))
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod image_tests {
+    use crate::image::ImageReference;
+
+    const DIGEST: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn parse() {
+        let image = ImageReference::parse("quay.io/wildfly/wildfly:26.1.3.Final-jdk17").unwrap();
+        assert_eq!("quay.io", image.registry);
+        assert_eq!("wildfly", image.namespace);
+        assert_eq!("wildfly", image.name);
+        assert_eq!(Some("26.1.3.Final-jdk17".to_string()), image.tag);
+        assert_eq!(None, image.digest);
+        assert_eq!(
+            "quay.io/wildfly/wildfly:26.1.3.Final-jdk17",
+            image.to_string()
+        );
+
+        let image = ImageReference::parse("jboss/wildfly:20.0.1.Final").unwrap();
+        assert_eq!("docker.io/jboss/wildfly:20.0.1.Final", image.to_string());
+        let image = ImageReference::parse("wildfly").unwrap();
+        assert_eq!("docker.io/library/wildfly", image.to_string());
+        let image = ImageReference::parse("index.docker.io/jboss/wildfly").unwrap();
+        assert_eq!("docker.io/jboss/wildfly", image.repository());
+
+        let image = ImageReference::parse(&format!(
+            "localhost:5000/team/sub/wildfly:latest@{}",
+            DIGEST
+        ))
+        .unwrap();
+        assert_eq!("localhost:5000", image.registry);
+        assert_eq!("team/sub", image.namespace);
+        assert_eq!("team/sub/wildfly", image.path());
+        assert_eq!(Some(DIGEST.to_string()), image.digest);
+
+        let image = ImageReference::parse("localhost/wildfly").unwrap();
+        assert_eq!("", image.namespace);
+        assert_eq!("localhost/wildfly", image.to_string());
+    }
+
+    #[test]
+    fn pin() {
+        let image = ImageReference::parse("quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21").unwrap();
+        assert_eq!(
+            format!("quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21@{}", DIGEST),
+            image.with_digest(DIGEST).to_string()
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(ImageReference::parse("").is_err());
+        assert!(ImageReference::parse("Quay.io/Wildfly").is_err());
+        assert!(ImageReference::parse("quay.io/wildfly/wildfly:").is_err());
+        assert!(ImageReference::parse("quay.io/wildfly/wildfly@sha256:abc").is_err());
+        assert!(ImageReference::parse("quay.io//wildfly").is_err());
+    }
+}
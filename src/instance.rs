@@ -0,0 +1,150 @@
+//! Instances of [WildFlyContainer]s with unique names and ports.
+//!
+//! An enumeration like "3x26.1" resolves to three identical [WildFlyContainer]s.
+//! [WildFlyInstance] adds a zero-based index, which counts the occurrences of the
+//! same version in order of appearance:
+//!
+//! | Instance | Name             | HTTP  | Management |
+//! |----------|------------------|-------|------------|
+//! | 0        | `wildfly-26.1`   | 8261  | 9261       |
+//! | 1        | `wildfly-26.1-1` | 14261 | 15261      |
+//! | 2        | `wildfly-26.1-2` | 20261 | 21261      |
+//!
+//! Distinct development builds like "dev@a,dev@b" use different slots and are named
+//! `wildfly-dev` and `wildfly-dev1` (see [WildFlyContainer::with_dev_slot]).
+//!
+//! See [VersionBased](crate::ports::VersionBased) for the details of the port scheme.
+
+use crate::ports::{PortStrategy, Ports, VersionBased};
+use crate::WildFlyContainer;
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// A [WildFlyContainer] together with its instance index
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub struct WildFlyInstance {
+    /// The container
+    pub wildfly: WildFlyContainer,
+
+    /// The zero-based index among all instances of the same version
+    pub index: u16,
+}
+
+impl WildFlyInstance {
+    /// Creates an instance with the given index.
+    pub fn new(wildfly: WildFlyContainer, index: u16) -> Self {
+        Self { wildfly, index }
+    }
+
+    /// Turns an enumeration of WildFly versions like "3x26.1,30..39"
+    /// into an array of [WildFlyInstance]s.
+    pub fn enumeration(enumeration: &str) -> Result<Vec<WildFlyInstance>> {
+        Ok(Self::instances(WildFlyContainer::enumeration(enumeration)?))
+    }
+
+    /// Numbers the given containers: Each occurrence of the same version gets the next index.
+    pub fn instances(containers: Vec<WildFlyContainer>) -> Vec<WildFlyInstance> {
+        let mut counter: HashMap<u32, u16> = HashMap::new();
+        containers
+            .into_iter()
+            .map(|wildfly| {
+                let index = counter.entry(wildfly.identifier).or_insert(0);
+                let instance = WildFlyInstance::new(wildfly, *index);
+                *index = index.saturating_add(1);
+                instance
+            })
+            .collect()
+    }
+
+    /// Returns the name "wildfly-<version>" for the first instance and
+    /// "wildfly-<version>-<index>" for all others.
+    pub fn name(&self) -> String {
+        if self.index == 0 {
+            format!("wildfly-{}", self.wildfly.display_version())
+        } else {
+            format!("wildfly-{}-{}", self.wildfly.display_version(), self.index)
+        }
+    }
+
+    /// Returns the HTTP port of this instance.
+    pub fn http_port(&self) -> Result<u16> {
+        Ok(self.ports(&VersionBased::default())?.http)
+    }
+
+    /// Returns the management port of this instance.
+    pub fn management_port(&self) -> Result<u16> {
+        Ok(self.ports(&VersionBased::default())?.management)
+    }
+
+    /// Returns the ports allocated by the given [PortStrategy].
+    pub fn ports(&self, strategy: &dyn PortStrategy) -> Result<Ports> {
+        strategy.instance_ports(self)
+    }
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod instance_tests {
+    use crate::instance::WildFlyInstance;
+    use crate::ports::{Sequential, VersionBased};
+    use std::collections::HashSet;
+
+    #[test]
+    fn multiplied_instances() {
+        let instances = WildFlyInstance::enumeration("3x26.1").expect("3x26.1");
+        assert_eq!(3, instances.len());
+        assert_eq!(
+            vec!["wildfly-26.1", "wildfly-26.1-1", "wildfly-26.1-2"],
+            instances.iter().map(|i| i.name()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![8261, 14261, 20261],
+            instances
+                .iter()
+                .map(|i| i.http_port().unwrap())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![9261, 15261, 21261],
+            instances
+                .iter()
+                .map(|i| i.management_port().unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn unique_across_enumeration() {
+        let instances = WildFlyInstance::enumeration("3x26.1,2x10..39,5x20,2xdev,dev@fix,dev#123")
+            .expect("DSL");
+        let names = instances.iter().map(|i| i.name()).collect::<HashSet<_>>();
+        assert_eq!(instances.len(), names.len());
+        let ports = instances
+            .iter()
+            .flat_map(|i| i.ports(&VersionBased::default()).unwrap().to_array())
+            .collect::<HashSet<_>>();
+        assert_eq!(6 * instances.len(), ports.len());
+    }
+
+    #[test]
+    fn indexes() {
+        let instances = WildFlyInstance::enumeration("2x10,26.1,20..21").expect("DSL");
+        assert_eq!(
+            vec![(100, 0), (100, 1), (200, 0), (210, 0), (261, 0)],
+            instances
+                .iter()
+                .map(|i| (i.wildfly.identifier, i.index))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn sequential_strategy() {
+        let strategy = Sequential::default();
+        let instances = WildFlyInstance::enumeration("2x26.1").expect("2x26.1");
+        let first = instances[0].ports(&strategy).unwrap();
+        let second = instances[1].ports(&strategy).unwrap();
+        assert_eq!((first.http, second.http), (8000, 8001));
+    }
+}
@@ -0,0 +1,413 @@
+//! Generates Kubernetes manifests for [WildFlyInstance]s.
+//!
+//! Each instance gets a Deployment and a Service, and optionally an Ingress or an
+//! OpenShift Route for the HTTP port. Resource names are derived from
+//! [WildFlyInstance::name] with dots replaced by dashes (e.g. `wildfly-26-1-2`).
+//! The platforms of a container restrict the nodes the pods are scheduled on.
+
+use crate::instance::WildFlyInstance;
+use crate::mirror;
+use crate::ports::CONTAINER_PORTS;
+use crate::run::standalone_command;
+use crate::yaml::{flow_sequence, quote};
+use anyhow::{bail, Result};
+
+pub use crate::image::DEFAULT_PLATFORM;
+
+/// How the HTTP port is exposed outside the cluster
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Exposure {
+    /// An Ingress with host `<name>.<domain>`
+    Ingress {
+        /// The domain appended to the resource name
+        domain: String,
+
+        /// An optional ingress class name
+        class: Option<String>,
+    },
+
+    /// An OpenShift Route with a generated host
+    Route,
+}
+
+/// Options for the generated manifests
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct KubernetesOptions {
+    /// An optional namespace for all resources
+    pub namespace: Option<String>,
+
+    /// The number of replicas per deployment
+    pub replicas: u32,
+
+    /// How to expose the HTTP port, if at all
+    pub exposure: Option<Exposure>,
+}
+
+impl Default for KubernetesOptions {
+    fn default() -> Self {
+        Self {
+            namespace: None,
+            replicas: 1,
+            exposure: None,
+        }
+    }
+}
+
+/// Returns the Kubernetes resource name of an instance like "wildfly-26-1".
+pub fn resource_name(instance: &WildFlyInstance) -> String {
+    instance.name().replace('.', "-")
+}
+
+/// Renders a multi-document YAML with a Deployment, a Service and an optional
+/// Ingress or Route per instance.
+///
+/// Fails for dev builds and local distributions, which have no image.
+pub fn kubernetes(instances: &[WildFlyInstance], options: &KubernetesOptions) -> Result<String> {
+    mirror::validate()?;
+    let mut documents = vec![];
+    for instance in instances {
+        if !instance.wildfly.has_image() {
+            bail!("no image for {}", instance.name())
+        }
+        documents.push(deployment(instance, options));
+        documents.push(service(instance, options));
+        match &options.exposure {
+            Some(Exposure::Ingress { domain, class }) => {
+                documents.push(ingress(instance, options, domain, class.as_deref()))
+            }
+            Some(Exposure::Route) => documents.push(route(instance, options)),
+            None => {}
+        }
+    }
+    Ok(documents.join("---\n"))
+}
+
+fn deployment(instance: &WildFlyInstance, options: &KubernetesOptions) -> String {
+    let name = resource_name(instance);
+    let mut lines = vec![
+        "apiVersion: apps/v1".to_string(),
+        "kind: Deployment".to_string(),
+    ];
+    lines.extend(metadata(instance, options));
+    lines.push("spec:".to_string());
+    lines.push(format!("  replicas: {}", options.replicas));
+    lines.push("  selector:".to_string());
+    lines.push("    matchLabels:".to_string());
+    lines.push(format!(
+        "      app.kubernetes.io/instance: {}",
+        quote(&name)
+    ));
+    lines.push("  template:".to_string());
+    lines.push("    metadata:".to_string());
+    lines.push("      labels:".to_string());
+    lines.extend(
+        labels(instance)
+            .iter()
+            .map(|label| format!("        {}", label)),
+    );
+    lines.push("    spec:".to_string());
+    lines.extend(scheduling(instance));
+    lines.push("      containers:".to_string());
+    lines.push("        - name: wildfly".to_string());
+    lines.push(format!(
+        "          image: {}",
+        quote(&instance.wildfly.image_name())
+    ));
+    lines.push(format!(
+        "          command: {}",
+        flow_sequence(&standalone_command())
+    ));
+    lines.push("          ports:".to_string());
+    lines.push("            - name: http".to_string());
+    lines.push(format!(
+        "              containerPort: {}",
+        CONTAINER_PORTS.http
+    ));
+    lines.push("            - name: management".to_string());
+    lines.push(format!(
+        "              containerPort: {}",
+        CONTAINER_PORTS.management
+    ));
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn service(instance: &WildFlyInstance, options: &KubernetesOptions) -> String {
+    let name = resource_name(instance);
+    let mut lines = vec!["apiVersion: v1".to_string(), "kind: Service".to_string()];
+    lines.extend(metadata(instance, options));
+    lines.push("spec:".to_string());
+    lines.push("  selector:".to_string());
+    lines.push(format!("    app.kubernetes.io/instance: {}", quote(&name)));
+    lines.push("  ports:".to_string());
+    lines.push("    - name: http".to_string());
+    lines.push(format!("      port: {}", CONTAINER_PORTS.http));
+    lines.push("      targetPort: http".to_string());
+    lines.push("    - name: management".to_string());
+    lines.push(format!("      port: {}", CONTAINER_PORTS.management));
+    lines.push("      targetPort: management".to_string());
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn ingress(
+    instance: &WildFlyInstance,
+    options: &KubernetesOptions,
+    domain: &str,
+    class: Option<&str>,
+) -> String {
+    let name = resource_name(instance);
+    let mut lines = vec![
+        "apiVersion: networking.k8s.io/v1".to_string(),
+        "kind: Ingress".to_string(),
+    ];
+    lines.extend(metadata(instance, options));
+    lines.push("spec:".to_string());
+    if let Some(class) = class {
+        lines.push(format!("  ingressClassName: {}", quote(class)));
+    }
+    lines.push("  rules:".to_string());
+    lines.push(format!(
+        "    - host: {}",
+        quote(&format!("{}.{}", name, domain))
+    ));
+    lines.push("      http:".to_string());
+    lines.push("        paths:".to_string());
+    lines.push("          - path: /".to_string());
+    lines.push("            pathType: Prefix".to_string());
+    lines.push("            backend:".to_string());
+    lines.push("              service:".to_string());
+    lines.push(format!("                name: {}", quote(&name)));
+    lines.push("                port:".to_string());
+    lines.push("                  name: http".to_string());
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn route(instance: &WildFlyInstance, options: &KubernetesOptions) -> String {
+    let name = resource_name(instance);
+    let mut lines = vec![
+        "apiVersion: route.openshift.io/v1".to_string(),
+        "kind: Route".to_string(),
+    ];
+    lines.extend(metadata(instance, options));
+    lines.push("spec:".to_string());
+    lines.push("  to:".to_string());
+    lines.push("    kind: Service".to_string());
+    lines.push(format!("    name: {}", quote(&name)));
+    lines.push("  port:".to_string());
+    lines.push("    targetPort: http".to_string());
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn metadata(instance: &WildFlyInstance, options: &KubernetesOptions) -> Vec<String> {
+    let mut lines = vec![
+        "metadata:".to_string(),
+        format!("  name: {}", quote(&resource_name(instance))),
+    ];
+    if let Some(namespace) = &options.namespace {
+        lines.push(format!("  namespace: {}", quote(namespace)));
+    }
+    lines.push("  labels:".to_string());
+    lines.extend(
+        labels(instance)
+            .iter()
+            .map(|label| format!("    {}", label)),
+    );
+    lines
+}
+
+fn labels(instance: &WildFlyInstance) -> Vec<String> {
+    vec![
+        "app.kubernetes.io/name: wildfly".to_string(),
+        format!(
+            "app.kubernetes.io/instance: {}",
+            quote(&resource_name(instance))
+        ),
+        format!(
+            "app.kubernetes.io/version: {}",
+            quote(&instance.wildfly.version.to_string())
+        ),
+        format!(
+            "wildfly.org/short-version: {}",
+            quote(&instance.wildfly.short_version)
+        ),
+        format!(
+            "wildfly.org/identifier: {}",
+            quote(&instance.wildfly.identifier.to_string())
+        ),
+    ]
+}
+
+/// Uses a node selector for single-platform images and a node affinity otherwise.
+fn scheduling(instance: &WildFlyInstance) -> Vec<String> {
+    let platforms = if instance.wildfly.platforms.is_empty() {
+        vec![DEFAULT_PLATFORM.to_string()]
+    } else {
+        instance.wildfly.platforms.clone()
+    };
+    let architectures = platforms
+        .iter()
+        .map(|platform| platform.split('/').nth(1).unwrap_or(platform).to_string())
+        .collect::<Vec<_>>();
+    if let [architecture] = architectures.as_slice() {
+        vec![
+            "      nodeSelector:".to_string(),
+            "        kubernetes.io/os: linux".to_string(),
+            format!("        kubernetes.io/arch: {}", quote(architecture)),
+        ]
+    } else {
+        vec![
+            "      nodeSelector:".to_string(),
+            "        kubernetes.io/os: linux".to_string(),
+            "      affinity:".to_string(),
+            "        nodeAffinity:".to_string(),
+            "          requiredDuringSchedulingIgnoredDuringExecution:".to_string(),
+            "            nodeSelectorTerms:".to_string(),
+            "              - matchExpressions:".to_string(),
+            "                  - key: kubernetes.io/arch".to_string(),
+            "                    operator: In".to_string(),
+            format!(
+                "                    values: {}",
+                flow_sequence(&architectures)
+            ),
+        ]
+    }
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod kubernetes_tests {
+    use crate::instance::WildFlyInstance;
+    use crate::kubernetes::{kubernetes, resource_name, Exposure, KubernetesOptions};
+
+    #[test]
+    fn resource_names() {
+        let instances = WildFlyInstance::enumeration("2x26.1").unwrap();
+        assert_eq!("wildfly-26-1", resource_name(&instances[0]));
+        assert_eq!("wildfly-26-1-1", resource_name(&instances[1]));
+    }
+
+    #[test]
+    fn deployment_and_service() {
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        let yaml = kubernetes(&instances, &KubernetesOptions::default()).unwrap();
+        assert_eq!(
+            r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: "wildfly-26-1"
+  labels:
+    app.kubernetes.io/name: wildfly
+    app.kubernetes.io/instance: "wildfly-26-1"
+    app.kubernetes.io/version: "26.1.3"
+    wildfly.org/short-version: "26.1"
+    wildfly.org/identifier: "261"
+spec:
+  replicas: 1
+  selector:
+    matchLabels:
+      app.kubernetes.io/instance: "wildfly-26-1"
+  template:
+    metadata:
+      labels:
+        app.kubernetes.io/name: wildfly
+        app.kubernetes.io/instance: "wildfly-26-1"
+        app.kubernetes.io/version: "26.1.3"
+        wildfly.org/short-version: "26.1"
+        wildfly.org/identifier: "261"
+    spec:
+      nodeSelector:
+        kubernetes.io/os: linux
+      affinity:
+        nodeAffinity:
+          requiredDuringSchedulingIgnoredDuringExecution:
+            nodeSelectorTerms:
+              - matchExpressions:
+                  - key: kubernetes.io/arch
+                    operator: In
+                    values: ["amd64", "arm64"]
+      containers:
+        - name: wildfly
+          image: "quay.io/wildfly/wildfly:26.1.3.Final-jdk17"
+          command: ["/opt/jboss/wildfly/bin/standalone.sh", "-b", "0.0.0.0", "-bmanagement", "0.0.0.0"]
+          ports:
+            - name: http
+              containerPort: 8080
+            - name: management
+              containerPort: 9990
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: "wildfly-26-1"
+  labels:
+    app.kubernetes.io/name: wildfly
+    app.kubernetes.io/instance: "wildfly-26-1"
+    app.kubernetes.io/version: "26.1.3"
+    wildfly.org/short-version: "26.1"
+    wildfly.org/identifier: "261"
+spec:
+  selector:
+    app.kubernetes.io/instance: "wildfly-26-1"
+  ports:
+    - name: http
+      port: 8080
+      targetPort: http
+    - name: management
+      port: 9990
+      targetPort: management
+"#,
+            yaml
+        );
+    }
+
+    #[test]
+    fn single_platform() {
+        let instances = WildFlyInstance::enumeration("20").unwrap();
+        let yaml = kubernetes(&instances, &KubernetesOptions::default()).unwrap();
+        assert!(yaml.contains(
+            "      nodeSelector:\n        kubernetes.io/os: linux\n        kubernetes.io/arch: \"amd64\"\n"
+        ));
+        assert!(!yaml.contains("affinity"));
+    }
+
+    #[test]
+    fn ingress() {
+        let instances = WildFlyInstance::enumeration("2x39").unwrap();
+        let options = KubernetesOptions {
+            namespace: Some("matrix".to_string()),
+            replicas: 2,
+            exposure: Some(Exposure::Ingress {
+                domain: "apps.example.com".to_string(),
+                class: Some("nginx".to_string()),
+            }),
+        };
+        let yaml = kubernetes(&instances, &options).unwrap();
+        assert_eq!(6, yaml.split("---\n").count());
+        assert_eq!(6, yaml.matches("  namespace: \"matrix\"\n").count());
+        assert!(yaml.contains("  replicas: 2\n"));
+        assert!(yaml.contains("  ingressClassName: \"nginx\"\n"));
+        assert!(yaml.contains("    - host: \"wildfly-39-0-1.apps.example.com\"\n"));
+    }
+
+    #[test]
+    fn route() {
+        let instances = WildFlyInstance::enumeration("39").unwrap();
+        let options = KubernetesOptions {
+            exposure: Some(Exposure::Route),
+            ..KubernetesOptions::default()
+        };
+        let yaml = kubernetes(&instances, &options).unwrap();
+        assert_eq!(3, yaml.split("---\n").count());
+        assert!(yaml.contains("kind: Route\n"));
+    }
+
+    #[test]
+    fn dev_has_no_image() {
+        let instances = WildFlyInstance::enumeration("dev").unwrap();
+        assert!(kubernetes(&instances, &KubernetesOptions::default()).is_err());
+    }
+}
@@ -3,6 +3,38 @@
 
 #![allow(deprecated)]
 
+pub mod airgap;
+pub mod availability;
+pub mod build;
+pub mod compose;
+pub mod containerfile;
+pub mod custom;
+pub mod dev;
+pub mod digests;
+pub mod discovery;
+pub mod image;
+pub mod instance;
+pub mod kubernetes;
+pub mod lint;
+pub mod local;
+pub mod mirror;
+pub mod pod;
+pub mod ports;
+pub mod registry;
+pub mod run;
+mod shell;
+pub mod systemd;
+#[cfg(test)]
+mod testing;
+pub mod updates;
+pub mod verify;
+mod yaml;
+
+use crate::custom::IMAGE_PREFIX;
+use crate::dev::DevBuild;
+use crate::image::ImageReference;
+use crate::local::{LocalDistribution, LOCAL_PREFIX};
+use crate::ports::{PortStrategy, Ports, VersionBased};
 use anyhow::{bail, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -15,16 +47,30 @@ pub static DEVELOPMENT_VERSION: &str = "dev";
 #[deprecated(since = "39.1.0", note = "This crate is deprecated. Use wildfly_meta instead: https://crates.io/crates/wildfly_meta")]
 pub static DEVELOPMENT_TAG: &str = "development";
 
-const HTTP_PORT_BASE: u16 = 8000;
-const MANAGEMENT_PORT_BASE: u16 = 9000;
+/// Versions with a major below this bound and a minor below [LEGACY_MINOR_BOUND]
+/// use the compact identifier `<major><minor>`.
+const LEGACY_MAJOR_BOUND: u32 = 100;
+const LEGACY_MINOR_BOUND: u32 = 10;
+
+/// The major version of the first WildFly release. Smaller majors would have identifiers
+/// in the range of the [DEV_SLOTS].
+const FIRST_MAJOR: u32 = 10;
+
+/// All other versions use `<major> * EXTENDED_FACTOR + <minor>`,
+/// which never overlaps with compact identifiers (always >= 1000).
+const EXTENDED_FACTOR: u32 = 1000;
+
+/// Development builds use the identifiers (and port offsets) below this bound,
+/// which no release uses (the first release 10.0 has identifier 100).
+pub const DEV_SLOTS: u32 = 100;
 
 lazy_static! {
-    static ref VERSION_RE: Regex = Regex::new(r"^(?<major>[0-9]{2})(?<dot>\.)?(?<minor>[0-9])?$").unwrap();
-    static ref VERSIONS_RE: Regex = Regex::new(r"^(?<major>[0-9]{2})\.?(?<minor>[0-9])?$").unwrap();
-    static ref WILDFLY_DEV: WildFlyContainer = WildFlyContainer::new(Version::new(0, 0, 0), Version::new(0, 0, 0), "", "", vec![]);
+    static ref VERSION_RE: Regex = Regex::new(r"^(?<major>[1-9][0-9]*)(\.(?<minor>0|[1-9][0-9]*))?$").unwrap();
+    static ref TAG_RE: Regex = Regex::new(r"^(?<major>[0-9]+)\.(?<minor>[0-9]+)\.(?<patch>[0-9]+)\.(?<suffix>.+)$").unwrap();
+    static ref WILDFLY_DEV: WildFlyContainer = WildFlyContainer::development(DevBuild::default());
 
     /// Static map with versions from 10 to 35
-    pub static ref VERSIONS: BTreeMap<u16, WildFlyContainer> = {
+    pub static ref VERSIONS: BTreeMap<u32, WildFlyContainer> = {
         let mut m = BTreeMap::new();
         // @formatter:off
         m.insert(identifier(10, 0), WildFlyContainer::new(Version::new(10, 0, 0), Version::new(2, 0, 10), "Final", "docker.io/jboss/wildfly", vec![]));
@@ -61,7 +107,8 @@ lazy_static! {
         m.insert(identifier(38, 0), WildFlyContainer::new(Version::new(38, 0, 1), Version::new(30, 0, 0), "Final-jdk21", "quay.io/wildfly/wildfly", vec!["linux/amd64", "linux/arm64", "linux/s390x", "linux/ppc64le"]));
         m.insert(identifier(39, 0), WildFlyContainer::new(Version::new(39, 0, 1), Version::new(31, 0, 3), "Final-2-jdk21", "quay.io/wildfly/wildfly", vec!["linux/amd64", "linux/arm64", "linux/s390x", "linux/ppc64le"]));
         // @formatter:on
-        m
+        let digests = digests::catalog();
+        m.into_iter().map(|(identifier, wildfly)| (identifier, digests.apply(wildfly))).collect()
     };
 }
 
@@ -69,10 +116,11 @@ lazy_static! {
 #[deprecated(since = "39.1.0", note = "This crate is deprecated. Use wildfly_meta instead: https://crates.io/crates/wildfly_meta")]
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 pub struct WildFlyContainer {
-    port_offset: u16,
+    port_offset: u32,
 
-    /// A unique identifier `<major><minor>`
-    pub identifier: u16,
+    /// A unique identifier: `<major><minor>` for versions up to 99.9,
+    /// `<major> * 1000 + <minor>` otherwise (e.g. 26010 for 26.10)
+    pub identifier: u32,
 
     /// The semantic version
     pub version: Version,
@@ -91,6 +139,20 @@ pub struct WildFlyContainer {
 
     /// The supported platforms
     pub platforms: Vec<String>,
+
+    /// The digests of the image manifests by platform like "linux/amd64"
+    /// (see [WildFlyContainer::pinned_image_name] and [digests])
+    pub digests: BTreeMap<String, String>,
+
+    /// The sources of development builds, `None` for released versions
+    pub dev: Option<DevBuild>,
+
+    /// The directory of local distributions, `None` for released versions
+    pub local: Option<LocalDistribution>,
+
+    /// The reference of a custom image, which replaces the image of this version or
+    /// the sources of a development build
+    pub custom_image: Option<String>,
 }
 
 impl WildFlyContainer {
@@ -101,62 +163,266 @@ impl WildFlyContainer {
         source_repository: &str,
         platforms: Vec<&str>,
     ) -> Self {
-        Self {
-            identifier: identifier(version.major as u16, version.minor as u16),
-            port_offset: (version.major * 10 + version.minor) as u16,
+        Self::try_new(version, core_version, suffix, source_repository, platforms)
+            .expect("version out of range")
+    }
+
+    /// Like [WildFlyContainer::new], but fails if the version has no identifier
+    /// (see [WildFlyContainer::identifier]).
+    pub fn try_new(
+        version: Version,
+        core_version: Version,
+        suffix: &str,
+        source_repository: &str,
+        platforms: Vec<&str>,
+    ) -> Result<Self> {
+        let Some((identifier, port_offset)) = u32::try_from(version.major)
+            .ok()
+            .zip(u32::try_from(version.minor).ok())
+            .and_then(|(major, minor)| {
+                try_identifier(major, minor).map(|id| (id, port_offset(major, minor)))
+            })
+        else {
+            bail!("version {} out of range", version)
+        };
+        Ok(Self {
+            identifier,
+            port_offset,
             short_version: format!("{}.{}", version.major, version.minor),
             version,
             core_version,
             suffix: suffix.to_string(),
             repository: source_repository.to_string(),
             platforms: platforms.iter().map(|s| s.to_string()).collect(),
+            digests: BTreeMap::new(),
+            dev: None,
+            local: None,
+            custom_image: None,
+        })
+    }
+
+    /// Adds the manifest digest of a platform like "linux/amd64".
+    pub fn with_digest(mut self, platform: &str, digest: &str) -> Self {
+        self.digests
+            .insert(platform.to_string(), digest.to_string());
+        self
+    }
+
+    /// Creates a development (build-from-source) container in slot 0.
+    pub fn development(build: DevBuild) -> Self {
+        Self {
+            identifier: 0,
+            port_offset: 0,
+            version: Version::new(0, 0, 0),
+            short_version: "0.0".to_string(),
+            core_version: Version::new(0, 0, 0),
+            suffix: String::new(),
+            repository: String::new(),
+            platforms: vec![],
+            digests: BTreeMap::new(),
+            dev: Some(build),
+            local: None,
+            custom_image: None,
+        }
+    }
+
+    /// Moves a development build to another slot, which becomes its identifier and port
+    /// offset. Distinct development builds need distinct slots to run side by side.
+    ///
+    /// Fails for released versions and slots outside of [DEV_SLOTS].
+    pub fn with_dev_slot(self, slot: u32) -> Result<Self> {
+        if !self.is_dev() {
+            bail!("{} is not a development build", self.display_version())
+        }
+        if slot >= DEV_SLOTS {
+            bail!("development slot {} out of range", slot)
         }
+        Ok(Self {
+            identifier: slot,
+            port_offset: slot,
+            ..self
+        })
     }
 
-    /// Returns the container image name, or the WildFly source repository URL for dev builds.
+    /// Returns the container image name, the reference of custom images, the source
+    /// repository URL for dev builds or the directory of local distributions.
+    ///
+    /// Image names are rewritten to the configured registry mirrors (see [mirror]).
+    /// Tags are mutable: Use [WildFlyContainer::pinned_image_name] for reproducible builds.
     pub fn image_name(&self) -> String {
-        if self.is_dev() {
-            "https://github.com/wildfly/wildfly.git".to_string()
+        if self.has_image() {
+            mirror::rewrite(&self.upstream_image_name())
+        } else {
+            self.upstream_image_name()
+        }
+    }
+
+    /// Returns the image name without applying registry mirrors.
+    pub fn upstream_image_name(&self) -> String {
+        if let Some(reference) = &self.custom_image {
+            reference.clone()
+        } else if let Some(build) = &self.dev {
+            build.repository.clone()
+        } else if let Some(local) = &self.local {
+            local.path.display().to_string()
         } else {
             format!("{}:{}.{}", self.repository, self.version, self.suffix)
         }
     }
 
-    /// Returns `true` if this is the development (build-from-source) container.
+    /// Looks up the catalog entry of a full image reference like
+    /// "quay.io/wildfly/wildfly:26.1.3.Final-jdk17" (inverse of [WildFlyContainer::image_name]).
+    /// Mirrored images are mapped back to their upstream name first.
+    ///
+    /// Docker Hub references may omit the registry ("jboss/wildfly:20.0.1.Final").
+    /// Fails with an explanation if the repository, micro version, suffix or (if the
+    /// catalog knows the digests) the digest don't match the catalog.
+    pub fn from_image_name(image: &str) -> Result<WildFlyContainer> {
+        mirror::validate()?;
+        let reference = ImageReference::parse(&mirror::restore(image))?;
+        let Some(tag) = &reference.tag else {
+            bail!("no tag in image {}", image)
+        };
+        let Some(c) = TAG_RE.captures(tag) else {
+            bail!("tag {} of image {} is not a WildFly version", tag, image)
+        };
+        let major: u32 = c["major"].parse()?;
+        let minor: u32 = c["minor"].parse()?;
+        let Some(wildfly) = try_identifier(major, minor).and_then(|id| VERSIONS.get(&id)) else {
+            bail!("unknown version {}.{} in image {}", major, minor, image)
+        };
+        let repository = reference.repository();
+        if wildfly.repository != repository {
+            bail!(
+                "{} is published to {}, not to {}",
+                wildfly.short_version,
+                wildfly.repository,
+                repository
+            )
+        }
+        let expected = format!("{}.{}", wildfly.version, wildfly.suffix);
+        if *tag != expected {
+            bail!(
+                "tag {} doesn't match {} of {} in the catalog",
+                tag,
+                expected,
+                wildfly.short_version
+            )
+        }
+        if let Some(digest) = &reference.digest {
+            if !wildfly.digests.is_empty() && !wildfly.digests.values().any(|d| d == digest) {
+                bail!("unknown digest {} for {}", digest, wildfly.short_version)
+            }
+        }
+        Ok(wildfly.clone())
+    }
+
+    /// Returns the parsed image reference, `None` for dev builds and local distributions.
+    pub fn image_reference(&self) -> Option<ImageReference> {
+        if self.has_image() {
+            ImageReference::parse(&self.image_name()).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the image name pinned to the digest of the given platform like
+    /// "quay.io/wildfly/wildfly:26.1.3.Final-jdk17@sha256:…".
+    ///
+    /// Fails if the digest of the platform is unknown.
+    pub fn pinned_image_name(&self, platform: &str) -> Result<String> {
+        let Some(reference) = self.image_reference() else {
+            bail!("no image for {}", self.image_name())
+        };
+        match self.digests.get(platform) {
+            Some(digest) => Ok(reference.with_digest(digest).to_string()),
+            None => bail!("no digest of {} for {}", platform, self.image_name()),
+        }
+    }
+
+    /// Returns `false` for dev builds and local distributions, which have no image,
+    /// unless a custom image replaces them (see [build::BuildPlan::wildfly]).
+    pub fn has_image(&self) -> bool {
+        self.custom_image.is_some() || (self.dev.is_none() && self.local.is_none())
+    }
+
+    /// Returns `true` if this is a development (build-from-source) container.
     pub fn is_dev(&self) -> bool {
-        self.identifier == 0
+        self.dev.is_some()
     }
 
-    /// Returns "dev" for development builds, otherwise the short version.
+    /// Returns the short version, "dev" for development builds in slot 0
+    /// and `dev<slot>` for all other development builds.
     pub fn display_version(&self) -> String {
-        if self.is_dev() {
+        if !self.is_dev() {
+            self.short_version.to_string()
+        } else if self.identifier == 0 {
             DEVELOPMENT_VERSION.to_string()
         } else {
-            self.short_version.to_string()
+            format!("{}{}", DEVELOPMENT_VERSION, self.identifier)
         }
     }
 
     /// Returns the HTTP port (base 8000 + port offset derived from major/minor version).
-    pub fn http_port(&self) -> u16 {
-        HTTP_PORT_BASE
-            .checked_add(self.port_offset)
-            .expect("HTTP port overflow")
+    ///
+    /// Fails for versions without ports: minors from 20 on and majors from 200 on
+    /// (see [VersionBased]).
+    pub fn http_port(&self) -> Result<u16> {
+        Ok(self.ports(&VersionBased::default())?.http)
     }
 
     /// Returns the management port (base 9000 + port offset derived from major/minor version).
-    pub fn management_port(&self) -> u16 {
-        MANAGEMENT_PORT_BASE
-            .checked_add(self.port_offset)
-            .expect("management port overflow")
+    ///
+    /// Fails for versions without ports: minors from 20 on and majors from 200 on
+    /// (see [VersionBased]).
+    pub fn management_port(&self) -> Result<u16> {
+        Ok(self.ports(&VersionBased::default())?.management)
     }
 
-    /// Turns an enumeration of WildFly versions like "3x10,23..26,5x28,34,dev"
+    /// Returns the ports allocated by the given [PortStrategy].
+    pub fn ports(&self, strategy: &dyn PortStrategy) -> Result<Ports> {
+        strategy.ports(self)
+    }
+
+    /// Looks up the [WildFlyContainer] which uses the given HTTP port (inverse of
+    /// [WildFlyContainer::http_port]). Ports of additional instances like 14261
+    /// resolve to their container as well.
+    pub fn from_http_port(port: u16) -> Result<WildFlyContainer> {
+        Self::from_port(port, "http")
+    }
+
+    /// Looks up the [WildFlyContainer] which uses the given management port (inverse of
+    /// [WildFlyContainer::management_port]). Ports of additional instances like 15261
+    /// resolve to their container as well.
+    pub fn from_management_port(port: u16) -> Result<WildFlyContainer> {
+        Self::from_port(port, "management")
+    }
+
+    fn from_port(port: u16, binding: &str) -> Result<WildFlyContainer> {
+        match VersionBased::default()
+            .resolve(port)
+            .into_iter()
+            .find(|resolved| resolved.mapping.name == binding)
+        {
+            Some(resolved) => Ok(resolved.instance.wildfly),
+            None => bail!("no version uses {} port {}", binding, port),
+        }
+    }
+
+    /// Turns an enumeration of WildFly versions like "3x10,23..26,5x28,34,dev@main"
     /// into an array of [WildFlyContainer]s.
+    ///
+    /// Distinct development builds get their own slot in order of appearance
+    /// (see [WildFlyContainer::with_dev_slot]): "dev@a,26.1,dev@b" puts "dev@a" in slot 0
+    /// and "dev@b" in slot 1. Repeated development builds like "2xdev" share one slot.
     pub fn enumeration(enumeration: &str) -> Result<Vec<WildFlyContainer>> {
         let mut result: Vec<WildFlyContainer> = vec![];
         let mut errors: Vec<String> = vec![];
         enumeration.split(',').for_each(|segment| {
-            if segment.contains("..") {
+            if segment.contains("..")
+                && !segment.contains(LOCAL_PREFIX)
+                && !segment.contains(IMAGE_PREFIX)
+            {
                 match Self::range(segment) {
                     Ok(interval) => result.extend(interval),
                     Err(e) => errors.push(e.to_string()),
@@ -169,7 +435,8 @@ impl WildFlyContainer {
             }
         });
         if errors.is_empty() {
-            result.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+            let mut result = Self::assign_dev_slots(result)?;
+            result.sort();
             Ok(result)
         } else if errors.len() > 1 {
             bail!("\n{}", errors.join("\n"))
@@ -191,79 +458,72 @@ impl WildFlyContainer {
         if parts.len() != 2 {
             bail!("invalid range syntax: '{}'", range)
         }
-        if parts[0] == DEVELOPMENT_VERSION || parts[1] == DEVELOPMENT_VERSION {
+        if parts[0].starts_with(DEVELOPMENT_VERSION) || parts[1].starts_with(DEVELOPMENT_VERSION) {
             bail!("'dev' is not allowed in range '{}'", range)
         }
         let from = match parts[0] {
-            "" => Some(VERSIONS.first_key_value().unwrap().1.clone()),
+            "" => VERSIONS.values().min().cloned(),
             _ => Self::version(parts[0]).ok(),
         };
         let to = match parts[1] {
-            "" => Some(VERSIONS.last_key_value().unwrap().1.clone()),
+            "" => VERSIONS.values().max().cloned(),
             _ => Self::version(parts[1]).ok(),
         };
         let from =
             from.ok_or_else(|| anyhow::anyhow!("invalid range bound: from '{}'", parts[0]))?;
         let to = to.ok_or_else(|| anyhow::anyhow!("invalid range bound: to '{}'", parts[1]))?;
-        match from.identifier.cmp(&to.identifier) {
+        match from.cmp(&to) {
             Ordering::Equal => Ok(vec![from.clone(); multiplier as usize]),
-            Ordering::Less => Ok(VERSIONS
-                .range(from.identifier..=to.identifier)
-                .flat_map(|(_, w)| vec![w.clone(); multiplier as usize])
-                .collect()),
+            Ordering::Less => {
+                // identifiers don't follow version order beyond 99.9, so filter by version
+                let mut interval = VERSIONS
+                    .values()
+                    .filter(|w| **w >= from && **w <= to)
+                    .collect::<Vec<_>>();
+                interval.sort();
+                Ok(interval
+                    .into_iter()
+                    .flat_map(|w| vec![w.clone(); multiplier as usize])
+                    .collect())
+            }
             Ordering::Greater => {
                 bail!("{} is greater than {}", from.identifier, to.identifier)
             }
         }
     }
 
-    /// Looks up a single [WildFlyContainer] version like "dev" or "22" or "3x26.1".
+    /// Looks up a single [WildFlyContainer] version like "dev" or "22" or "3x26.1" or "2xdev@main".
     pub fn versions(short_version: &str) -> Result<Vec<WildFlyContainer>> {
         if let Some((multiplier, short_version)) = Self::multiplier(short_version) {
-            if short_version == "dev" {
-                Ok(vec![WILDFLY_DEV.clone(); multiplier as usize])
-            } else {
-                match VERSIONS_RE.captures(short_version) {
-                    Some(c) => {
-                        let major: u16 = c["major"].parse()?;
-                        let minor: u16 = match c.name("minor") {
-                            Some(m) => m.as_str().parse()?,
-                            None => 0,
-                        };
-                        match VERSIONS.get(&identifier(major, minor)) {
-                            Some(wildfly) => Ok(vec![wildfly.clone(); multiplier as usize]),
-                            None => bail!("unknown version {}", short_version),
-                        }
-                    }
-                    None => bail!("invalid version '{}'", short_version),
-                }
-            }
+            Ok(vec![Self::version(short_version)?; multiplier as usize])
         } else {
             bail!("invalid multiplier in '{}'", short_version)
         }
     }
 
     /// Looks up a single [WildFlyContainer] version like "dev" or "22" or "26.1".
+    /// Development builds accept a reference like "dev@main" or "dev#12345"
+    /// (see [DevBuild::parse]), "local:<path>" inspects a local distribution
+    /// (see [local::inspect]) and "image:<reference>" selects a custom image
+    /// (see [custom::parse]).
     pub fn version(short_version: &str) -> Result<WildFlyContainer> {
-        if short_version == "dev" {
-            Ok(WILDFLY_DEV.clone())
+        if short_version.starts_with(DEVELOPMENT_VERSION) {
+            Ok(Self::development(DevBuild::parse(short_version)?))
+        } else if let Some(path) = short_version.strip_prefix(LOCAL_PREFIX) {
+            local::inspect(std::path::Path::new(path))
+        } else if short_version.starts_with(IMAGE_PREFIX) {
+            custom::parse(short_version)
         } else {
             match VERSION_RE.captures(short_version) {
                 Some(c) => {
-                    let major: u16 = c["major"].parse()?;
-                    let dot = c.name("dot").is_some();
-                    let minor = c.name("minor");
-                    if dot && minor.is_none() {
-                        bail!("invalid version '{}'", short_version)
-                    } else {
-                        let minor: u16 = match minor {
-                            Some(m) => m.as_str().parse()?,
-                            None => 0,
-                        };
-                        match VERSIONS.get(&identifier(major, minor)) {
-                            Some(wildfly) => Ok(wildfly.clone()),
-                            None => bail!("unknown version {}", short_version),
-                        }
+                    let major: u32 = c["major"].parse()?;
+                    let minor: u32 = match c.name("minor") {
+                        Some(m) => m.as_str().parse()?,
+                        None => 0,
+                    };
+                    match try_identifier(major, minor).and_then(|id| VERSIONS.get(&id)) {
+                        Some(wildfly) => Ok(wildfly.clone()),
+                        None => bail!("unknown version {}", short_version),
                     }
                 }
                 None => bail!("invalid version '{}'", short_version),
@@ -271,43 +531,99 @@ impl WildFlyContainer {
         }
     }
 
-    /// Looks up a [WildFlyContainer] by its numeric identifier (`major * 10 + minor`).
-    pub fn lookup(identifier: u16) -> Result<WildFlyContainer> {
+    /// Looks up a [WildFlyContainer] by its numeric identifier (see [WildFlyContainer::identifier]).
+    pub fn lookup(identifier: u32) -> Result<WildFlyContainer> {
         match VERSIONS.get(&identifier) {
             Some(wildfly) => Ok(wildfly.clone()),
             None => bail!("unknown version {}", identifier),
         }
     }
 
-    fn multiplier(range_or_short_version: &str) -> Option<(u16, &str)> {
-        if range_or_short_version.contains('x') {
-            let parts = range_or_short_version.split('x').collect::<Vec<&str>>();
-            if parts.len() == 2 {
-                if !parts[1].is_empty() {
-                    if let Ok(multiplier) = parts[0].parse::<u16>() {
-                        if multiplier > 0 {
-                            Some((multiplier, parts[1]))
-                        } else {
-                            None
+    fn assign_dev_slots(containers: Vec<WildFlyContainer>) -> Result<Vec<WildFlyContainer>> {
+        let mut builds: Vec<DevBuild> = vec![];
+        containers
+            .into_iter()
+            .map(|wildfly| match &wildfly.dev {
+                Some(build) => {
+                    let slot = match builds.iter().position(|b| b == build) {
+                        Some(slot) => slot,
+                        None => {
+                            builds.push(build.clone());
+                            builds.len() - 1
                         }
-                    } else {
-                        None
-                    }
-                } else {
+                    };
+                    wildfly.with_dev_slot(slot as u32)
+                }
+                None => Ok(wildfly),
+            })
+            .collect()
+    }
+
+    fn multiplier(range_or_short_version: &str) -> Option<(u16, &str)> {
+        // references of development builds, paths and images may contain an 'x'
+        // ("dev@fix", "2xdev@fix", "local:/opt/wildfly-x", "image:example.org/wildfly")
+        let is_pseudo = |value: &str| {
+            value.starts_with(DEVELOPMENT_VERSION)
+                || value.starts_with(LOCAL_PREFIX)
+                || value.starts_with(IMAGE_PREFIX)
+        };
+        if is_pseudo(range_or_short_version) {
+            return Some((1, range_or_short_version));
+        }
+        match range_or_short_version.split_once('x') {
+            Some((multiplier, rest)) => {
+                if rest.is_empty() || (rest.contains('x') && !is_pseudo(rest)) {
                     None
+                } else {
+                    match multiplier.parse::<u16>() {
+                        Ok(multiplier) if multiplier > 0 => Some((multiplier, rest)),
+                        _ => None,
+                    }
                 }
-            } else {
-                None
             }
-        } else {
-            Some((1, range_or_short_version))
+            None => Some((1, range_or_short_version)),
         }
     }
 }
 
 impl Ord for WildFlyContainer {
+    /// Orders by version. Development builds come first, ordered by their slot.
+    ///
+    /// Containers of the same version and slot are ordered by their sources (dev build,
+    /// local distribution, custom image) and finally by the remaining fields, so the order
+    /// is consistent with equality.
     fn cmp(&self, other: &Self) -> Ordering {
-        self.identifier.cmp(&other.identifier)
+        (self.version.major, self.version.minor, self.identifier)
+            .cmp(&(other.version.major, other.version.minor, other.identifier))
+            .then_with(|| {
+                (&self.dev, &self.local, &self.custom_image).cmp(&(
+                    &other.dev,
+                    &other.local,
+                    &other.custom_image,
+                ))
+            })
+            .then_with(|| {
+                (
+                    &self.version,
+                    &self.core_version,
+                    &self.suffix,
+                    &self.repository,
+                    &self.platforms,
+                    &self.digests,
+                    &self.short_version,
+                    self.port_offset,
+                )
+                    .cmp(&(
+                        &other.version,
+                        &other.core_version,
+                        &other.suffix,
+                        &other.repository,
+                        &other.platforms,
+                        &other.digests,
+                        &other.short_version,
+                        other.port_offset,
+                    ))
+            })
     }
 }
 
@@ -317,15 +633,46 @@ impl PartialOrd for WildFlyContainer {
     }
 }
 
-fn identifier(major: u16, minor: u16) -> u16 {
-    major * 10 + minor
+/// Computes the identifier of a major/minor version.
+///
+/// Versions up to 99.9 keep the compact `<major><minor>` form (e.g. 261 for 26.1).
+/// All other versions use `<major> * 1000 + <minor>` (e.g. 26010 for 26.10 or
+/// 100000 for 100.0), so minors up to 999 never collide.
+///
+/// Returns `None` if the major version is below 10, the minor version is 1000 or above,
+/// or the identifier exceeds `u32::MAX`.
+fn try_identifier(major: u32, minor: u32) -> Option<u32> {
+    if major < FIRST_MAJOR {
+        None
+    } else if major < LEGACY_MAJOR_BOUND && minor < LEGACY_MINOR_BOUND {
+        Some(major * LEGACY_MINOR_BOUND + minor)
+    } else if minor < EXTENDED_FACTOR {
+        major
+            .checked_mul(EXTENDED_FACTOR)
+            .and_then(|id| id.checked_add(minor))
+    } else {
+        None
+    }
+}
+
+/// The port offset inside the band of the version (see [VersionBased]): the identifier
+/// for versions up to 99.9, `<major % 100><minor % 10>` otherwise.
+fn port_offset(major: u32, minor: u32) -> u32 {
+    (major % LEGACY_MAJOR_BOUND) * LEGACY_MINOR_BOUND + minor % LEGACY_MINOR_BOUND
+}
+
+fn identifier(major: u32, minor: u32) -> u32 {
+    try_identifier(major, minor).expect("version out of range")
 }
 
 // ------------------------------------------------------ tests
 
 #[cfg(test)]
 mod wildfly_tests {
-    use crate::{WildFlyContainer, VERSIONS};
+    use crate::dev::GitRef;
+    use crate::{identifier, try_identifier, WildFlyContainer, VERSIONS};
+    use semver::Version;
+    use std::cmp::Ordering;
 
     #[test]
     fn multiplier_ok() {
@@ -334,6 +681,14 @@ mod wildfly_tests {
         assert_eq!(WildFlyContainer::multiplier("1x30"), Some((1, "30")));
         assert_eq!(WildFlyContainer::multiplier("foo"), Some((1, "foo")));
         assert_eq!(WildFlyContainer::multiplier(""), Some((1, "")));
+        assert_eq!(
+            WildFlyContainer::multiplier("dev@fix"),
+            Some((1, "dev@fix"))
+        );
+        assert_eq!(
+            WildFlyContainer::multiplier("2xdev@fix"),
+            Some((2, "dev@fix"))
+        );
     }
 
     #[test]
@@ -342,11 +697,14 @@ mod wildfly_tests {
         assert_eq!(WildFlyContainer::multiplier("x25"), None);
         assert_eq!(WildFlyContainer::multiplier("25x"), None);
         assert_eq!(WildFlyContainer::multiplier("10xx20"), None);
+        assert_eq!(WildFlyContainer::multiplier("0xdev"), None);
     }
 
     #[test]
     fn single_version_ok() {
         assert!(WildFlyContainer::version("dev").is_ok());
+        assert!(WildFlyContainer::version("dev@main").is_ok());
+        assert!(WildFlyContainer::version("dev#12345").is_ok());
         assert!(WildFlyContainer::version("10").is_ok());
         assert!(WildFlyContainer::version("25").is_ok());
         assert!(WildFlyContainer::version("25.0").is_ok());
@@ -376,6 +734,15 @@ mod wildfly_tests {
         assert!(WildFlyContainer::version("10.10").is_err());
         assert!(WildFlyContainer::version("99").is_err());
         assert!(WildFlyContainer::version("10.").is_err());
+        assert!(WildFlyContainer::version("026").is_err());
+        assert!(WildFlyContainer::version("26.01").is_err());
+        assert!(WildFlyContainer::version("026.1").is_err());
+        assert!(WildFlyContainer::version("26.10").is_err());
+        assert!(WildFlyContainer::version("100").is_err());
+        assert!(WildFlyContainer::version("26.1000").is_err());
+        assert!(WildFlyContainer::version("99999999999").is_err());
+        assert!(WildFlyContainer::version("dev@").is_err());
+        assert!(WildFlyContainer::version("devel").is_err());
     }
 
     #[test]
@@ -419,6 +786,7 @@ mod wildfly_tests {
         assert!(WildFlyContainer::range("dev..dev").is_err());
         assert!(WildFlyContainer::range("10..dev").is_err());
         assert!(WildFlyContainer::range("dev..20").is_err());
+        assert!(WildFlyContainer::range("dev@main..").is_err());
         assert!(WildFlyContainer::range("20..10").is_err());
         assert!(WildFlyContainer::range("10..20..30").is_err());
     }
@@ -521,6 +889,54 @@ mod wildfly_tests {
         assert_eq!(dev.display_version(), "dev");
     }
 
+    #[test]
+    fn dev_builds() {
+        let dev = WildFlyContainer::version("dev").unwrap();
+        assert_eq!("https://github.com/wildfly/wildfly.git", dev.image_name());
+        let fork = WildFlyContainer::version("dev@myfork/fix").unwrap();
+        assert!(fork.is_dev());
+        assert_eq!("https://github.com/myfork/wildfly.git", fork.image_name());
+        let tag = WildFlyContainer::version("dev@31.0.0.Final").unwrap();
+        assert_eq!(
+            Some(semver::Version::new(31, 0, 0)),
+            tag.dev.unwrap().expected_version
+        );
+        let result = WildFlyContainer::enumeration("26.1,2xdev@fix").expect("dev DSL");
+        assert_eq!(3, result.len());
+        assert!(result.iter().filter(|w| w.is_dev()).count() == 2);
+        assert!(!WildFlyContainer::version("26.1").unwrap().is_dev());
+    }
+
+    #[test]
+    fn dev_slots() {
+        let result = WildFlyContainer::enumeration("dev@b,26.1,2xdev@a,dev@b").expect("dev slots");
+        assert_eq!(
+            vec![0, 0, 1, 1, 261],
+            result.iter().map(|w| w.identifier).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["dev", "dev", "dev1", "dev1", "26.1"],
+            result
+                .iter()
+                .map(|w| w.display_version())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            GitRef::Branch("b".to_string()),
+            result[0].dev.as_ref().unwrap().reference
+        );
+        assert_eq!(8001, result[2].http_port().unwrap());
+        assert!(result[0] < result[2]);
+        assert!(WildFlyContainer::version("26.1")
+            .unwrap()
+            .with_dev_slot(1)
+            .is_err());
+        assert!(WildFlyContainer::version("dev")
+            .unwrap()
+            .with_dev_slot(100)
+            .is_err());
+    }
+
     #[test]
     fn display_version_regular() {
         let wf = WildFlyContainer::version("25").unwrap();
@@ -529,6 +945,186 @@ mod wildfly_tests {
         assert_eq!(wf.display_version(), "26.1");
     }
 
+    #[test]
+    fn identifier_compact() {
+        assert_eq!(identifier(10, 0), 100);
+        assert_eq!(identifier(26, 1), 261);
+        assert_eq!(identifier(99, 9), 999);
+    }
+
+    #[test]
+    fn identifier_extended() {
+        assert_eq!(identifier(26, 10), 26010);
+        assert_eq!(identifier(27, 0), 270);
+        assert_eq!(identifier(100, 0), 100000);
+        assert_eq!(identifier(100, 1), 100001);
+        assert_eq!(identifier(123, 45), 123045);
+        assert_eq!(try_identifier(26, 1000), None);
+        assert_eq!(try_identifier(u32::MAX, 0), None);
+    }
+
+    #[test]
+    fn major_below_ten() {
+        assert_eq!(try_identifier(9, 9), None);
+        assert_eq!(try_identifier(0, 0), None);
+        let wf = |major, minor| {
+            WildFlyContainer::try_new(
+                Version::new(major, minor, 0),
+                Version::new(0, 0, 0),
+                "",
+                "",
+                vec![],
+            )
+        };
+        assert!(wf(5, 0).is_err());
+        assert!(wf(9, 12).is_err());
+        assert!(wf(10, 0).is_ok());
+        assert!(WildFlyContainer::version("5").is_err());
+        assert_eq!(0, WildFlyContainer::version("dev").unwrap().identifier);
+    }
+
+    #[test]
+    fn ports_out_of_range() {
+        let wf = |major, minor| {
+            WildFlyContainer::new(
+                Version::new(major, minor, 0),
+                Version::new(0, 0, 0),
+                "",
+                "",
+                vec![],
+            )
+        };
+        assert_eq!(50260, wf(26, 10).http_port().unwrap());
+        assert_eq!(51269, wf(26, 19).management_port().unwrap());
+        for wildfly in [wf(26, 20), wf(26, 999), wf(200, 0), wf(1000, 1)] {
+            assert!(wildfly.http_port().is_err());
+            assert!(wildfly.management_port().is_err());
+        }
+    }
+
+    #[test]
+    fn order_by_version() {
+        let wf = |major, minor| {
+            WildFlyContainer::new(
+                Version::new(major, minor, 0),
+                Version::new(0, 0, 0),
+                "",
+                "",
+                vec![],
+            )
+        };
+        let (wf_26_9, wf_26_10, wf_27, wf_100) = (wf(26, 9), wf(26, 10), wf(27, 0), wf(100, 0));
+        assert!(wf_26_9 < wf_26_10);
+        assert!(wf_26_10 < wf_27);
+        assert!(wf_27 < wf_100);
+        assert_ne!(wf_26_10.identifier, wf_27.identifier);
+    }
+
+    #[test]
+    fn order_consistent_with_eq() {
+        let a = WildFlyContainer::version("dev@a").unwrap();
+        let b = WildFlyContainer::version("dev@b").unwrap();
+        assert_eq!(a.identifier, b.identifier);
+        assert_ne!(a, b);
+        assert_ne!(Ordering::Equal, a.cmp(&b));
+        assert_eq!(Ordering::Equal, a.cmp(&a.clone()));
+
+        let wildfly = WildFlyContainer::version("26.1").unwrap();
+        let custom = crate::custom::custom_image("localhost/wildfly:26.1", None).unwrap();
+        assert_ne!(Ordering::Equal, wildfly.cmp(&custom));
+        let pinned = wildfly
+            .clone()
+            .with_digest("linux/amd64", "sha256:0123456789abcdef0123456789abcdef");
+        assert_ne!(Ordering::Equal, wildfly.cmp(&pinned));
+
+        let mut containers = vec![b.clone(), a.clone(), custom, wildfly, b, a];
+        containers.sort();
+        containers.dedup();
+        assert_eq!(4, containers.len());
+    }
+
+    #[test]
+    fn from_image_name_ok() {
+        let _lock = crate::testing::MIRRORS_LOCK.lock().unwrap();
+        for wf in VERSIONS.values() {
+            assert_eq!(
+                *wf,
+                WildFlyContainer::from_image_name(&wf.image_name()).unwrap()
+            );
+        }
+        let wf = WildFlyContainer::from_image_name("jboss/wildfly:20.0.1.Final").unwrap();
+        assert_eq!(200, wf.identifier);
+        let wf = WildFlyContainer::from_image_name("index.docker.io/jboss/wildfly:10.1.0.Final")
+            .unwrap();
+        assert_eq!(101, wf.identifier);
+        let wf = WildFlyContainer::from_image_name(
+            "quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+        )
+        .unwrap();
+        assert_eq!(390, wf.identifier);
+    }
+
+    #[test]
+    fn pinned_image_name() {
+        let digest = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        let wf = WildFlyContainer::version("26.1")
+            .unwrap()
+            .with_digest("linux/arm64", digest);
+        assert_eq!(
+            format!("quay.io/wildfly/wildfly:26.1.3.Final-jdk17@{}", digest),
+            wf.pinned_image_name("linux/arm64").unwrap()
+        );
+        assert!(wf.pinned_image_name("linux/amd64").is_err());
+        assert!(WildFlyContainer::version("dev")
+            .unwrap()
+            .pinned_image_name("linux/amd64")
+            .is_err());
+        let reference = wf.image_reference().unwrap();
+        assert_eq!("quay.io", reference.registry);
+        assert_eq!(Some("26.1.3.Final-jdk17".to_string()), reference.tag);
+    }
+
+    #[test]
+    fn from_image_name_err() {
+        let err = |image: &str| {
+            WildFlyContainer::from_image_name(image)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            "no tag in image quay.io/wildfly/wildfly",
+            err("quay.io/wildfly/wildfly")
+        );
+        assert_eq!(
+            "no tag in image localhost:5000/wildfly",
+            err("localhost:5000/wildfly")
+        );
+        assert_eq!(
+            "tag latest of image quay.io/wildfly/wildfly:latest is not a WildFly version",
+            err("quay.io/wildfly/wildfly:latest")
+        );
+        assert_eq!(
+            "unknown version 9.0 in image jboss/wildfly:9.0.2.Final",
+            err("jboss/wildfly:9.0.2.Final")
+        );
+        assert_eq!(
+            "26.1 is published to quay.io/wildfly/wildfly, not to docker.io/jboss/wildfly",
+            err("jboss/wildfly:26.1.3.Final-jdk17")
+        );
+        assert_eq!(
+            "20.0 is published to docker.io/jboss/wildfly, not to registry.local/wildfly",
+            err("registry.local/wildfly:20.0.1.Final")
+        );
+        assert_eq!(
+            "tag 26.1.2.Final-jdk17 doesn't match 26.1.3.Final-jdk17 of 26.1 in the catalog",
+            err("quay.io/wildfly/wildfly:26.1.2.Final-jdk17")
+        );
+        assert_eq!(
+            "tag 26.1.3.Final-jdk11 doesn't match 26.1.3.Final-jdk17 of 26.1 in the catalog",
+            err("quay.io/wildfly/wildfly:26.1.3.Final-jdk11")
+        );
+    }
+
     #[test]
     fn lookup_ok() {
         assert!(WildFlyContainer::lookup(100).is_ok());
@@ -542,6 +1138,34 @@ mod wildfly_tests {
         assert!(WildFlyContainer::lookup(999).is_err());
     }
 
+    #[test]
+    fn from_port_ok() {
+        let wf = WildFlyContainer::from_http_port(8261).expect("8261");
+        assert_eq!(261, wf.identifier);
+        let wf = WildFlyContainer::from_management_port(9390).expect("9390");
+        assert_eq!(390, wf.identifier);
+        let wf = WildFlyContainer::from_http_port(14100).expect("14100");
+        assert_eq!(100, wf.identifier);
+        assert!(WildFlyContainer::from_http_port(8000).unwrap().is_dev());
+        for wf in VERSIONS.values() {
+            let http_port = wf.http_port().unwrap();
+            let management_port = wf.management_port().unwrap();
+            assert_eq!(*wf, WildFlyContainer::from_http_port(http_port).unwrap());
+            assert_eq!(
+                *wf,
+                WildFlyContainer::from_management_port(management_port).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn from_port_err() {
+        assert!(WildFlyContainer::from_http_port(80).is_err());
+        assert!(WildFlyContainer::from_http_port(8999).is_err());
+        assert!(WildFlyContainer::from_http_port(9261).is_err());
+        assert!(WildFlyContainer::from_management_port(8261).is_err());
+    }
+
     #[test]
     fn enumeration_ok() {
         let result = WildFlyContainer::enumeration("3x10,23..26,5x28,34,dev").expect("full DSL");
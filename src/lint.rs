@@ -0,0 +1,391 @@
+//! Checks the consistency of a catalog.
+//!
+//! [lint] checks the invariants of [VERSIONS](crate::VERSIONS) for any catalog, e.g. one loaded
+//! from a file, and [validate] fails if one of them is violated:
+//!
+//! - the identifiers match the versions,
+//! - the core versions increase with the WildFly versions,
+//! - the JDK of the suffix is at least the minimum Java version of the WildFly version
+//!   (see [JAVA_SUPPORT] and [lint_with_java_support]),
+//! - the repositories only switch forward (see [REGISTRY_ORDER]) and never back,
+//! - the platforms are known values (see [KNOWN_PLATFORMS]),
+//! - the minor versions of each major version have no gaps.
+
+use crate::image::{ImageReference, DOCKER_HUB};
+use crate::{try_identifier, WildFlyContainer};
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// The minimum Java version by the first WildFly major version which requires it.
+///
+/// Only the minimum is checked: A JDK newer than the versions a WildFly release was
+/// tested with is accepted.
+pub const JAVA_SUPPORT: [(u64, u32); 3] = [(10, 8), (27, 11), (35, 17)];
+
+/// The registries in the order the images moved along
+pub const REGISTRY_ORDER: [&str; 2] = [DOCKER_HUB, "quay.io"];
+
+/// The platforms of the WildFly images
+pub const KNOWN_PLATFORMS: [&str; 4] =
+    ["linux/amd64", "linux/arm64", "linux/ppc64le", "linux/s390x"];
+
+lazy_static! {
+    static ref SUFFIX_JDK_RE: Regex = Regex::new(r"-jdk(?<jdk>[0-9]+)$").unwrap();
+}
+
+/// The invariants checked by [lint]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Check {
+    /// The identifier matches the version and is unique.
+    Identifier,
+
+    /// The core version is greater than the core version of the previous WildFly version.
+    CoreVersion,
+
+    /// The JDK of the suffix is supported by the WildFly version.
+    Jdk,
+
+    /// The repository is the repository of the previous version or a later one.
+    Repository,
+
+    /// The platforms are known and unique.
+    Platform,
+
+    /// The previous minor version of the same major version is part of the catalog.
+    Gap,
+}
+
+/// A violated invariant
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Issue {
+    /// The violated invariant
+    pub check: Check,
+
+    /// The short version of the offending entry like "26.1"
+    pub version: String,
+
+    /// What's wrong
+    pub message: String,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.version, self.message)
+    }
+}
+
+/// Checks the invariants of the catalog and returns the issues ordered by version.
+pub fn lint(catalog: &[WildFlyContainer]) -> Vec<Issue> {
+    lint_with_java_support(catalog, &JAVA_SUPPORT)
+}
+
+/// Like [lint], but checks the JDKs against the given table instead of [JAVA_SUPPORT].
+///
+/// `java_support` lists the minimum Java version by the first WildFly major version which
+/// requires it, in ascending order. Versions before the first entry accept any JDK.
+pub fn lint_with_java_support(
+    catalog: &[WildFlyContainer],
+    java_support: &[(u64, u32)],
+) -> Vec<Issue> {
+    let mut versions = catalog.iter().collect::<Vec<_>>();
+    versions.sort_by(|a, b| a.version.cmp(&b.version));
+    let mut issues = vec![];
+    let mut issue = |wildfly: &WildFlyContainer, check: Check, message: String| {
+        issues.push(Issue {
+            check,
+            version: wildfly.short_version.clone(),
+            message,
+        })
+    };
+
+    let mut identifiers: BTreeMap<u32, &WildFlyContainer> = BTreeMap::new();
+    let mut repositories: Vec<&str> = vec![];
+    let mut previous: Option<&WildFlyContainer> = None;
+    for wildfly in versions {
+        // identifier
+        let expected = u32::try_from(wildfly.version.major)
+            .ok()
+            .zip(u32::try_from(wildfly.version.minor).ok())
+            .and_then(|(major, minor)| try_identifier(major, minor));
+        if expected != Some(wildfly.identifier) {
+            issue(
+                wildfly,
+                Check::Identifier,
+                format!(
+                    "identifier {} doesn't match the version",
+                    wildfly.identifier
+                ),
+            );
+        }
+        if let Some(other) = identifiers.insert(wildfly.identifier, wildfly) {
+            issue(
+                wildfly,
+                Check::Identifier,
+                format!(
+                    "identifier {} is already used by {}",
+                    wildfly.identifier, other.version
+                ),
+            );
+            // the other checks are about the first entry
+            continue;
+        }
+
+        // core version
+        if let Some(previous) = previous {
+            if wildfly.core_version <= previous.core_version {
+                issue(
+                    wildfly,
+                    Check::CoreVersion,
+                    format!(
+                        "core version {} doesn't increase (core version {} for {})",
+                        wildfly.core_version, previous.core_version, previous.version
+                    ),
+                );
+            }
+        }
+
+        // JDK
+        if let Some(c) = SUFFIX_JDK_RE.captures(&wildfly.suffix) {
+            let jdk = c["jdk"].parse::<u32>().unwrap_or_default();
+            let minimum = java_support
+                .iter()
+                .rev()
+                .find(|(major, _)| wildfly.version.major >= *major)
+                .map(|(_, java)| *java);
+            if minimum.is_some_and(|minimum| jdk < minimum) {
+                issue(
+                    wildfly,
+                    Check::Jdk,
+                    format!(
+                        "JDK {} of suffix {} is not supported (Java {} or later)",
+                        jdk,
+                        wildfly.suffix,
+                        minimum.unwrap_or_default()
+                    ),
+                );
+            }
+        }
+
+        // repository
+        let repository = wildfly.repository.as_str();
+        if repositories.last() != Some(&repository) {
+            if repositories.contains(&repository) {
+                issue(
+                    wildfly,
+                    Check::Repository,
+                    format!("switches back to repository {}", repository),
+                );
+            } else if let Some(last) = repositories.last() {
+                if registry_rank(repository) < registry_rank(last) {
+                    issue(
+                        wildfly,
+                        Check::Repository,
+                        format!("switches from {} back to {}", last, repository),
+                    );
+                }
+            }
+            repositories.push(repository);
+        }
+
+        // platforms
+        for (index, platform) in wildfly.platforms.iter().enumerate() {
+            if !KNOWN_PLATFORMS.contains(&platform.as_str()) {
+                issue(
+                    wildfly,
+                    Check::Platform,
+                    format!("unknown platform {}", platform),
+                );
+            } else if wildfly.platforms[..index].contains(platform) {
+                issue(
+                    wildfly,
+                    Check::Platform,
+                    format!("duplicate platform {}", platform),
+                );
+            }
+        }
+
+        // gaps
+        let minor = wildfly.version.minor;
+        if minor > 0 {
+            let previous_minor = previous
+                .filter(|p| p.version.major == wildfly.version.major)
+                .map(|p| p.version.minor);
+            if previous_minor.is_none_or(|previous| previous + 1 < minor) {
+                let missing = previous_minor.map_or(0, |previous| previous + 1);
+                issue(
+                    wildfly,
+                    Check::Gap,
+                    format!("{}.{} is missing", wildfly.version.major, missing),
+                );
+            }
+        }
+
+        previous = Some(wildfly);
+    }
+    issues
+}
+
+/// Fails with all issues if the catalog violates one of the invariants.
+pub fn validate(catalog: &[WildFlyContainer]) -> Result<()> {
+    let issues = lint(catalog);
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        bail!(
+            "invalid catalog: {}",
+            issues
+                .iter()
+                .map(Issue::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+fn registry_rank(repository: &str) -> Option<usize> {
+    let registry = ImageReference::parse(repository).ok()?.registry;
+    REGISTRY_ORDER.iter().position(|r| *r == registry)
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod lint_tests {
+    use crate::lint::{lint, lint_with_java_support, validate, Check};
+    use crate::{WildFlyContainer, VERSIONS};
+    use semver::Version;
+
+    fn wf(
+        version: (u64, u64, u64),
+        core: (u64, u64, u64),
+        suffix: &str,
+        repository: &str,
+        platforms: Vec<&str>,
+    ) -> WildFlyContainer {
+        WildFlyContainer::new(
+            Version::new(version.0, version.1, version.2),
+            Version::new(core.0, core.1, core.2),
+            suffix,
+            repository,
+            platforms,
+        )
+    }
+
+    #[test]
+    fn catalog() {
+        let catalog = VERSIONS.values().cloned().collect::<Vec<_>>();
+        assert_eq!(
+            Vec::<String>::new(),
+            lint(&catalog)
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert!(validate(&catalog).is_ok());
+        assert!(validate(&[]).is_ok());
+    }
+
+    #[test]
+    fn issues() {
+        let docker = "docker.io/jboss/wildfly";
+        let quay = "quay.io/wildfly/wildfly";
+        let catalog = vec![
+            wf((19, 0, 0), (11, 0, 0), "Final", docker, vec![]),
+            WildFlyContainer {
+                identifier: 191,
+                ..wf((19, 2, 0), (11, 1, 1), "Final", docker, vec![])
+            },
+            wf((23, 0, 2), (15, 0, 1), "Final", quay, vec![]),
+            wf((24, 0, 0), (14, 0, 0), "Final", docker, vec![]),
+            wf(
+                (26, 1, 3),
+                (18, 1, 2),
+                "Final-jdk17",
+                quay,
+                vec!["linux/amd64", "linux/arm"],
+            ),
+            wf(
+                (35, 0, 1),
+                (27, 0, 1),
+                "Final-jdk11",
+                quay,
+                vec!["linux/amd64", "linux/amd64"],
+            ),
+        ];
+        let issues = lint(&catalog);
+        assert_eq!(
+            vec![
+                "19.2: identifier 191 doesn't match the version",
+                "19.2: 19.1 is missing",
+                "24.0: core version 14.0.0 doesn't increase (core version 15.0.1 for 23.0.2)",
+                "24.0: switches back to repository docker.io/jboss/wildfly",
+                "26.1: switches back to repository quay.io/wildfly/wildfly",
+                "26.1: unknown platform linux/arm",
+                "26.1: 26.0 is missing",
+                "35.0: JDK 11 of suffix Final-jdk11 is not supported (Java 17 or later)",
+                "35.0: duplicate platform linux/amd64",
+            ],
+            issues.iter().map(|i| i.to_string()).collect::<Vec<_>>()
+        );
+        assert_eq!(Check::Identifier, issues[0].check);
+        assert!(validate(&catalog).is_err());
+    }
+
+    #[test]
+    fn repository_order() {
+        let catalog = vec![
+            wf(
+                (23, 0, 2),
+                (15, 0, 1),
+                "Final",
+                "quay.io/wildfly/wildfly",
+                vec![],
+            ),
+            wf(
+                (24, 0, 0),
+                (16, 0, 0),
+                "Final",
+                "docker.io/jboss/wildfly",
+                vec![],
+            ),
+        ];
+        let issues = lint(&catalog);
+        assert_eq!(1, issues.len());
+        assert_eq!(Check::Repository, issues[0].check);
+        assert_eq!(
+            "24.0: switches from quay.io/wildfly/wildfly back to docker.io/jboss/wildfly",
+            issues[0].to_string()
+        );
+    }
+
+    #[test]
+    fn duplicate_identifier() {
+        let wildfly = WildFlyContainer::version("26").unwrap();
+        let issues = lint(&[wildfly.clone(), wildfly]);
+        assert_eq!(1, issues.len());
+        assert_eq!(Check::Identifier, issues[0].check);
+    }
+
+    #[test]
+    fn java_support() {
+        let catalog = vec![wf(
+            (39, 0, 1),
+            (31, 0, 1),
+            "Final-2-jdk21",
+            "quay.io/wildfly/wildfly",
+            vec![],
+        )];
+        assert!(lint(&catalog).is_empty());
+        let issues = lint_with_java_support(&catalog, &[(27, 11), (39, 25)]);
+        assert_eq!(1, issues.len());
+        assert_eq!(Check::Jdk, issues[0].check);
+        assert_eq!(
+            "39.0: JDK 21 of suffix Final-2-jdk21 is not supported (Java 25 or later)",
+            issues[0].to_string()
+        );
+        assert!(lint_with_java_support(&catalog, &[]).is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+//! Inspects locally built WildFly distributions.
+//!
+//! The enumeration DSL accepts `local:<path>` segments like "local:/tmp/wildfly-31.0.0.Final".
+//! The version is read from `version.txt` in the root of the distribution:
+//!
+//! ```text
+//! WildFly Full 26.1.3.Final (WildFly Core 18.1.2.Final) - 2023-01-31
+//! ```
+//!
+//! If `version.txt` is missing, the product module named by `bin/product.conf` provides the
+//! version (`JBoss-Product-Release-Version` in its manifest), and the version of the
+//! `wildfly-controller` jar the core version.
+
+use crate::WildFlyContainer;
+use anyhow::{anyhow, bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::Version;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The prefix of local distributions in the enumeration DSL
+pub const LOCAL_PREFIX: &str = "local:";
+
+const BASE_LAYER: &str = "modules/system/layers/base";
+
+lazy_static! {
+    static ref VERSION_TXT_RE: Regex = Regex::new(
+        r"(?<version>[0-9]+\.[0-9]+\.[0-9]+)\.(?<suffix>[A-Za-z0-9-]+) \(WildFly Core (?<core>[0-9]+\.[0-9]+\.[0-9]+)"
+    )
+    .unwrap();
+    static ref RELEASE_RE: Regex =
+        Regex::new(r"^(?<version>[0-9]+\.[0-9]+\.[0-9]+)\.(?<suffix>[A-Za-z0-9-]+)$").unwrap();
+    static ref CONTROLLER_RE: Regex =
+        Regex::new(r"^wildfly-controller-(?<core>[0-9]+\.[0-9]+\.[0-9]+)\.[A-Za-z0-9-]+\.jar$")
+            .unwrap();
+}
+
+/// A WildFly distribution on the local file system
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct LocalDistribution {
+    /// The root directory of the distribution
+    pub path: PathBuf,
+
+    /// The product slot from `bin/product.conf` like "wildfly-full", if any
+    pub product: Option<String>,
+}
+
+/// Inspects the distribution in the given directory and returns a [WildFlyContainer]
+/// with the detected version, suffix and core version.
+pub fn inspect(path: &Path) -> Result<WildFlyContainer> {
+    if !path.is_dir() {
+        bail!("no distribution in {}", path.display())
+    }
+    let product = product(path);
+    let (version, suffix, core_version) = match fs::read_to_string(path.join("version.txt")) {
+        Ok(content) => match VERSION_TXT_RE.captures(&content) {
+            Some(c) => (
+                Version::parse(&c["version"])?,
+                c["suffix"].to_string(),
+                Version::parse(&c["core"])?,
+            ),
+            None => bail!("unknown version in {}/version.txt", path.display()),
+        },
+        Err(_) => {
+            let Some(slot) = &product else {
+                bail!(
+                    "neither version.txt nor bin/product.conf in {}",
+                    path.display()
+                )
+            };
+            let (version, suffix) = product_version(path, slot)?;
+            (version, suffix, core_version(path)?)
+        }
+    };
+    WildFlyContainer::try_new(version, core_version, &suffix, "", vec![]).map(|wildfly| {
+        WildFlyContainer {
+            local: Some(LocalDistribution {
+                path: path.to_path_buf(),
+                product,
+            }),
+            ..wildfly
+        }
+    })
+}
+
+fn product(path: &Path) -> Option<String> {
+    fs::read_to_string(path.join("bin/product.conf"))
+        .ok()?
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("slot="))
+        .map(|slot| slot.trim().to_string())
+}
+
+fn product_version(path: &Path, slot: &str) -> Result<(Version, String)> {
+    let manifest = path
+        .join(BASE_LAYER)
+        .join("org/jboss/as/product")
+        .join(slot)
+        .join("dir/META-INF/MANIFEST.MF");
+    let content = fs::read_to_string(&manifest)
+        .map_err(|e| anyhow!("unable to read {}: {}", manifest.display(), e))?;
+    let release = content
+        .lines()
+        .find_map(|line| line.strip_prefix("JBoss-Product-Release-Version:"))
+        .map(str::trim)
+        .ok_or_else(|| anyhow!("no release version in {}", manifest.display()))?;
+    match RELEASE_RE.captures(release) {
+        Some(c) => Ok((Version::parse(&c["version"])?, c["suffix"].to_string())),
+        None => bail!(
+            "unknown release version {} in {}",
+            release,
+            manifest.display()
+        ),
+    }
+}
+
+fn core_version(path: &Path) -> Result<Version> {
+    let controller = path.join(BASE_LAYER).join("org/jboss/as/controller/main");
+    let entries = fs::read_dir(&controller)
+        .map_err(|e| anyhow!("unable to read {}: {}", controller.display(), e))?;
+    for entry in entries.flatten() {
+        if let Some(c) = CONTROLLER_RE.captures(&entry.file_name().to_string_lossy()) {
+            return Ok(Version::parse(&c["core"])?);
+        }
+    }
+    bail!("no core version in {}", controller.display())
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod local_tests {
+    use crate::local::inspect;
+    use crate::WildFlyContainer;
+    use semver::Version;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn distribution(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("wildfly-local-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        for (file, content) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn version_txt() {
+        let root = distribution(
+            "version-txt",
+            &[
+                (
+                    "version.txt",
+                    "WildFly Full 26.1.3.Final (WildFly Core 18.1.2.Final) - 2023-01-31\n",
+                ),
+                ("bin/product.conf", "slot=wildfly-full\n"),
+            ],
+        );
+        let wildfly = inspect(&root).unwrap();
+        assert_eq!(Version::new(26, 1, 3), wildfly.version);
+        assert_eq!(Version::new(18, 1, 2), wildfly.core_version);
+        assert_eq!("Final", wildfly.suffix);
+        assert_eq!(261, wildfly.identifier);
+        assert_eq!(8261, wildfly.http_port().unwrap());
+        let local = wildfly.local.unwrap();
+        assert_eq!(root, local.path);
+        assert_eq!(Some("wildfly-full".to_string()), local.product);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn modules() {
+        let root = distribution(
+            "modules",
+            &[
+                ("bin/product.conf", "slot=wildfly-full\n"),
+                (
+                    "modules/system/layers/base/org/jboss/as/product/wildfly-full/dir/META-INF/MANIFEST.MF",
+                    "Manifest-Version: 1.0\nJBoss-Product-Release-Version: 32.0.0.Beta1\n",
+                ),
+                (
+                    "modules/system/layers/base/org/jboss/as/controller/main/wildfly-controller-24.0.0.Beta3.jar",
+                    "",
+                ),
+            ],
+        );
+        let dsl = format!("2xlocal:{}", root.display());
+        let wildfly = WildFlyContainer::versions(&dsl).unwrap();
+        assert_eq!(2, wildfly.len());
+        assert_eq!(Version::new(32, 0, 0), wildfly[0].version);
+        assert_eq!("Beta1", wildfly[0].suffix);
+        assert_eq!(Version::new(24, 0, 0), wildfly[0].core_version);
+        assert!(!wildfly[0].has_image());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(inspect(&PathBuf::from("/no/such/wildfly")).is_err());
+        let root = distribution("invalid", &[("version.txt", "foo\n")]);
+        assert!(inspect(&root).is_err());
+        fs::remove_dir_all(&root).unwrap();
+        let root = distribution("empty", &[]);
+        assert!(inspect(&root).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
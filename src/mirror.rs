@@ -0,0 +1,279 @@
+//! Rewrites image names to registry mirrors.
+//!
+//! A [Mirrors] table maps prefixes of image names to mirrors, e.g. "quay.io/wildfly" to
+//! "mirror.corp/wildfly". [WildFlyContainer::image_name](crate::WildFlyContainer::image_name)
+//! and thus all generators apply the table configured by [set_mirrors]. Initially, the table
+//! is read from the environment variable `WILDFLY_MIRRORS`, which contains comma separated
+//! rules:
+//!
+//! ```text
+//! WILDFLY_MIRRORS=quay.io/wildfly=mirror.corp/wildfly,docker.io/jboss=mirror.corp/jboss
+//! ```
+//!
+//! If the variable is invalid, [mirrors], [validate] and the generators fail until
+//! [set_mirrors] replaces the table. Image names are not rewritten in the meantime.
+
+use anyhow::{anyhow, bail, Result};
+use lazy_static::lazy_static;
+use std::env;
+use std::sync::RwLock;
+
+/// The environment variable with the initial rewrite rules
+pub const MIRRORS_ENV: &str = "WILDFLY_MIRRORS";
+
+lazy_static! {
+    static ref MIRRORS: RwLock<Result<Mirrors, String>> =
+        RwLock::new(Mirrors::from_env().map_err(|e| e.to_string()));
+}
+
+/// A table of rewrite rules from image name prefixes to mirrors
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Mirrors {
+    rules: Vec<(String, String)>,
+}
+
+impl Mirrors {
+    /// Adds a rule which rewrites images starting with `source` to start with `target`.
+    ///
+    /// Prefixes match whole path segments: "quay.io/wildfly" matches
+    /// "quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21", but not "quay.io/wildfly-x/foo".
+    pub fn with(mut self, source: &str, target: &str) -> Self {
+        let source = source.trim_end_matches('/').to_string();
+        let target = target.trim_end_matches('/').to_string();
+        self.rules.retain(|(s, _)| *s != source);
+        self.rules.push((source, target));
+        self
+    }
+
+    /// Parses comma separated rules like "quay.io/wildfly=mirror.corp/wildfly".
+    pub fn parse(rules: &str) -> Result<Mirrors> {
+        let mut mirrors = Mirrors::default();
+        for rule in rules.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            match rule.split_once('=') {
+                Some((source, target))
+                    if !source.trim().is_empty() && !target.trim().is_empty() =>
+                {
+                    mirrors = mirrors.with(source.trim(), target.trim());
+                }
+                _ => bail!("invalid mirror rule '{}'", rule),
+            }
+        }
+        Ok(mirrors)
+    }
+
+    /// Parses the rules of the environment variable [MIRRORS_ENV].
+    /// Returns an empty table if the variable is not set.
+    pub fn from_env() -> Result<Mirrors> {
+        Self::from_var(env::var(MIRRORS_ENV).ok())
+    }
+
+    fn from_var(rules: Option<String>) -> Result<Mirrors> {
+        match rules {
+            Some(rules) => {
+                Self::parse(&rules).map_err(|e| anyhow!("invalid {}: {}", MIRRORS_ENV, e))
+            }
+            None => Ok(Mirrors::default()),
+        }
+    }
+
+    /// Returns the rules as (source, target) pairs.
+    pub fn rules(&self) -> &[(String, String)] {
+        &self.rules
+    }
+
+    /// Returns `true` if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the rule with the longest source matching the image.
+    pub fn rule_for(&self, image: &str) -> Option<(&str, &str)> {
+        longest_match(
+            image,
+            self.rules.iter().map(|(s, t)| (s.as_str(), t.as_str())),
+        )
+    }
+
+    /// Rewrites the image using the rule with the longest matching source.
+    pub fn rewrite(&self, image: &str) -> String {
+        replace_prefix(image, self.rule_for(image))
+    }
+
+    /// Reverts [Mirrors::rewrite]: Returns the upstream name of a mirrored image.
+    pub fn restore(&self, image: &str) -> String {
+        replace_prefix(
+            image,
+            longest_match(
+                image,
+                self.rules.iter().map(|(s, t)| (t.as_str(), s.as_str())),
+            ),
+        )
+    }
+}
+
+/// Returns a copy of the rewrite table in use.
+///
+/// Fails if the table was read from an invalid environment variable [MIRRORS_ENV].
+pub fn mirrors() -> Result<Mirrors> {
+    match MIRRORS.read() {
+        Ok(mirrors) => mirrors.clone().map_err(|e| anyhow!(e)),
+        Err(_) => bail!("the mirror table is poisoned"),
+    }
+}
+
+/// Replaces the rewrite table in use, including an invalid table from [MIRRORS_ENV].
+pub fn set_mirrors(mirrors: Mirrors) {
+    if let Ok(mut current) = MIRRORS.write() {
+        *current = Ok(mirrors);
+    }
+}
+
+/// Fails if the table in use was read from an invalid environment variable [MIRRORS_ENV].
+/// The generators call this before they use image names.
+pub fn validate() -> Result<()> {
+    mirrors().map(|_| ())
+}
+
+/// Rewrites the image using the table in use.
+pub fn rewrite(image: &str) -> String {
+    match MIRRORS.read().as_deref() {
+        Ok(Ok(mirrors)) if !mirrors.is_empty() => mirrors.rewrite(image),
+        _ => image.to_string(),
+    }
+}
+
+/// Returns the upstream name of an image using the table in use.
+pub fn restore(image: &str) -> String {
+    match MIRRORS.read().as_deref() {
+        Ok(Ok(mirrors)) if !mirrors.is_empty() => mirrors.restore(image),
+        _ => image.to_string(),
+    }
+}
+
+fn longest_match<'a>(
+    image: &str,
+    rules: impl Iterator<Item = (&'a str, &'a str)>,
+) -> Option<(&'a str, &'a str)> {
+    rules
+        .filter(|(from, _)| {
+            image
+                .strip_prefix(from)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', ':', '@']))
+        })
+        .max_by_key(|(from, _)| from.len())
+}
+
+fn replace_prefix(image: &str, rule: Option<(&str, &str)>) -> String {
+    match rule {
+        Some((from, to)) => format!("{}{}", to, &image[from.len()..]),
+        None => image.to_string(),
+    }
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod mirror_tests {
+    use crate::compose::{compose, ComposeOptions};
+    use crate::instance::WildFlyInstance;
+    use crate::kubernetes::{kubernetes, KubernetesOptions};
+    use crate::mirror::{mirrors, set_mirrors, Mirrors};
+    use crate::ports::VersionBased;
+    use crate::run::RunSpec;
+    use crate::testing::MIRRORS_LOCK;
+    use crate::WildFlyContainer;
+
+    #[test]
+    fn rewrite() {
+        let mirrors = Mirrors::default()
+            .with("quay.io", "mirror.corp/quay")
+            .with("quay.io/wildfly", "mirror.corp/wildfly/");
+        assert_eq!(
+            "mirror.corp/wildfly/wildfly:26.1.3.Final-jdk17",
+            mirrors.rewrite("quay.io/wildfly/wildfly:26.1.3.Final-jdk17")
+        );
+        assert_eq!(
+            "mirror.corp/quay/wildfly-x/foo:1",
+            mirrors.rewrite("quay.io/wildfly-x/foo:1")
+        );
+        assert_eq!(
+            "docker.io/jboss/wildfly:20.0.1.Final",
+            mirrors.rewrite("docker.io/jboss/wildfly:20.0.1.Final")
+        );
+        assert_eq!(
+            "quay.io/wildfly/wildfly:26.1.3.Final-jdk17",
+            mirrors.restore("mirror.corp/wildfly/wildfly:26.1.3.Final-jdk17")
+        );
+        assert_eq!(
+            Some(("quay.io/wildfly", "mirror.corp/wildfly")),
+            mirrors.rule_for("quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21")
+        );
+        assert_eq!(None, mirrors.rule_for("docker.io/jboss/wildfly"));
+    }
+
+    #[test]
+    fn parse() {
+        let mirrors = Mirrors::parse(
+            " quay.io/wildfly=mirror.corp/wildfly, docker.io/jboss=mirror.corp/jboss,",
+        )
+        .unwrap();
+        assert_eq!(2, mirrors.rules().len());
+        assert_eq!(
+            "mirror.corp/jboss/wildfly:10.0.0.Final",
+            mirrors.rewrite("docker.io/jboss/wildfly:10.0.0.Final")
+        );
+        assert!(Mirrors::parse("").unwrap().is_empty());
+        assert!(Mirrors::parse("quay.io").is_err());
+        assert!(Mirrors::parse("quay.io=").is_err());
+        assert!(Mirrors::parse("=mirror.corp").is_err());
+    }
+
+    #[test]
+    fn from_var() {
+        assert!(Mirrors::from_var(None).unwrap().is_empty());
+        assert_eq!(
+            1,
+            Mirrors::from_var(Some("quay.io=mirror.corp".to_string()))
+                .unwrap()
+                .rules()
+                .len()
+        );
+        assert_eq!(
+            "invalid WILDFLY_MIRRORS: invalid mirror rule 'quay.io'",
+            Mirrors::from_var(Some("quay.io".to_string()))
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn generators() {
+        // rewrites a single image, which no other test uses by name
+        let upstream = "docker.io/jboss/wildfly:10.1.0.Final";
+        let mirrored = "mirror.test/jboss/wildfly:10.1.0.Final";
+        let _lock = MIRRORS_LOCK.lock().unwrap();
+        let previous = mirrors().unwrap();
+        set_mirrors(previous.clone().with(upstream, mirrored));
+
+        let wildfly = WildFlyContainer::version("10.1").unwrap();
+        assert_eq!(mirrored, wildfly.image_name());
+        assert_eq!(upstream, wildfly.upstream_image_name());
+        assert_eq!(
+            wildfly,
+            WildFlyContainer::from_image_name(mirrored).unwrap()
+        );
+        let instances = WildFlyInstance::enumeration("10.1").unwrap();
+        let strategy = VersionBased::default();
+        let spec = RunSpec::new(&instances[0], &strategy).unwrap();
+        assert_eq!(mirrored, spec.image);
+        let yaml = compose(&instances, &strategy, &ComposeOptions::default()).unwrap();
+        assert!(yaml.contains(mirrored));
+        assert!(!yaml.contains(upstream));
+        let yaml = kubernetes(&instances, &KubernetesOptions::default()).unwrap();
+        assert!(yaml.contains(mirrored));
+        assert!(!yaml.contains(upstream));
+
+        set_mirrors(previous);
+        assert_eq!(upstream, wildfly.image_name());
+    }
+}
@@ -0,0 +1,172 @@
+//! Generates a single Pod for all [WildFlyInstance]s of an enumeration.
+//!
+//! The YAML works with `podman kube play` (and `podman kube down`), so a whole version
+//! matrix is started and torn down at once. All containers of a pod share one network
+//! namespace. That's why each container starts WildFly with the socket binding port
+//! offset `<position> * 100`: The first container listens on 8080 and 9990, the second
+//! one on 8180 and 10090 and so on. The host ports are the ones of the [PortStrategy].
+//!
+//! The JPDA debug port is not a socket binding and is shifted only in the port mapping.
+//! If you publish it, configure the debug agent to listen on the shifted port.
+
+use crate::instance::WildFlyInstance;
+use crate::kubernetes::resource_name;
+use crate::ports::PortStrategy;
+use crate::run::{run_specs, standalone_command, RunSpec};
+use crate::yaml::{flow_sequence, quote};
+use anyhow::{anyhow, bail, Result};
+
+/// The difference between the socket binding port offsets of two containers in the pod
+pub const POD_PORT_OFFSET_STEP: u16 = 100;
+
+/// Renders a Pod named `name` with one container per instance.
+///
+/// Fails for dev builds, local distributions and if the strategy fails to allocate ports.
+pub fn pod(
+    name: &str,
+    instances: &[WildFlyInstance],
+    strategy: &dyn PortStrategy,
+) -> Result<String> {
+    let specs = run_specs(instances, strategy)?;
+    let names = instances.iter().map(resource_name).collect::<Vec<_>>();
+    pod_specs(name, &names, &specs)
+}
+
+/// Renders a Pod named `name` with one container per run specification.
+///
+/// `names` contains the container names, which must be valid DNS labels, one per
+/// specification. Platforms and extra engine arguments of the specifications are ignored.
+pub fn pod_specs(name: &str, names: &[String], specs: &[RunSpec]) -> Result<String> {
+    if names.len() != specs.len() {
+        bail!(
+            "{} container names for {} containers in pod {}",
+            names.len(),
+            specs.len(),
+            name
+        )
+    }
+    let mut lines = vec![
+        "apiVersion: v1".to_string(),
+        "kind: Pod".to_string(),
+        "metadata:".to_string(),
+        format!("  name: {}", quote(name)),
+        "  labels:".to_string(),
+        "    app.kubernetes.io/name: wildfly".to_string(),
+        "spec:".to_string(),
+        "  containers:".to_string(),
+    ];
+    for (position, (container, spec)) in names.iter().zip(specs).enumerate() {
+        let offset = u16::try_from(position)
+            .ok()
+            .and_then(|position| position.checked_mul(POD_PORT_OFFSET_STEP))
+            .ok_or_else(|| anyhow!("too many containers in pod {}", name))?;
+        let mut command = if spec.command.is_empty() {
+            standalone_command()
+        } else {
+            spec.command.clone()
+        };
+        command.push(format!("-Djboss.socket.binding.port-offset={}", offset));
+
+        lines.push(format!("    - name: {}", quote(container)));
+        lines.push(format!("      image: {}", quote(&spec.image)));
+        lines.push(format!("      command: {}", flow_sequence(&command)));
+        let mappings = spec.mappings();
+        if !mappings.is_empty() {
+            lines.push("      ports:".to_string());
+            for mapping in mappings {
+                let container_port = mapping.container.checked_add(offset).ok_or_else(|| {
+                    anyhow!("{} port overflow in container {}", mapping.name, container)
+                })?;
+                lines.push(format!("        - containerPort: {}", container_port));
+                lines.push(format!("          hostPort: {}", mapping.host));
+            }
+        }
+        if !spec.env.is_empty() {
+            lines.push("      env:".to_string());
+            for (key, value) in &spec.env {
+                lines.push(format!("        - name: {}", quote(key)));
+                lines.push(format!("          value: {}", quote(value)));
+            }
+        }
+    }
+    lines.push(String::new());
+    Ok(lines.join("\n"))
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod pod_tests {
+    use crate::instance::WildFlyInstance;
+    use crate::pod::{pod, pod_specs};
+    use crate::ports::VersionBased;
+    use crate::run::run_specs;
+
+    #[test]
+    fn matrix() {
+        let instances = WildFlyInstance::enumeration("2x26.1").unwrap();
+        let yaml = pod("matrix", &instances, &VersionBased::default()).unwrap();
+        assert_eq!(
+            r#"apiVersion: v1
+kind: Pod
+metadata:
+  name: "matrix"
+  labels:
+    app.kubernetes.io/name: wildfly
+spec:
+  containers:
+    - name: "wildfly-26-1"
+      image: "quay.io/wildfly/wildfly:26.1.3.Final-jdk17"
+      command: ["/opt/jboss/wildfly/bin/standalone.sh", "-b", "0.0.0.0", "-bmanagement", "0.0.0.0", "-Djboss.socket.binding.port-offset=0"]
+      ports:
+        - containerPort: 8080
+          hostPort: 8261
+        - containerPort: 9990
+          hostPort: 9261
+    - name: "wildfly-26-1-1"
+      image: "quay.io/wildfly/wildfly:26.1.3.Final-jdk17"
+      command: ["/opt/jboss/wildfly/bin/standalone.sh", "-b", "0.0.0.0", "-bmanagement", "0.0.0.0", "-Djboss.socket.binding.port-offset=100"]
+      ports:
+        - containerPort: 8180
+          hostPort: 14261
+        - containerPort: 10090
+          hostPort: 15261
+"#,
+            yaml
+        );
+    }
+
+    #[test]
+    fn distinct_ports() {
+        let instances = WildFlyInstance::enumeration("3x26.1,30..39").unwrap();
+        let yaml = pod("matrix", &instances, &VersionBased::default()).unwrap();
+        let ports = |prefix: &str| {
+            let ports = yaml
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix(prefix))
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            let unique = ports.iter().collect::<std::collections::HashSet<_>>().len();
+            (ports.len(), unique)
+        };
+        assert_eq!((26, 26), ports("- containerPort: "));
+        assert_eq!((26, 26), ports("hostPort: "));
+    }
+
+    #[test]
+    fn dev_has_no_image() {
+        let instances = WildFlyInstance::enumeration("dev").unwrap();
+        assert!(pod("matrix", &instances, &VersionBased::default()).is_err());
+    }
+
+    #[test]
+    fn names_mismatch() {
+        let instances = WildFlyInstance::enumeration("2x26.1").unwrap();
+        let specs = run_specs(&instances, &VersionBased::default()).unwrap();
+        let names = vec!["wildfly-26-1".to_string()];
+        assert_eq!(
+            "1 container names for 2 containers in pod matrix",
+            pod_specs("matrix", &names, &specs).unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,624 @@
+//! Port allocation strategies for [WildFlyContainer]s.
+//!
+//! The default [VersionBased] strategy derives the ports from the version
+//! (e.g. 8261 and 9261 for WildFly 26.1). Use [VersionBased::with_bases] to move
+//! the ports into a different range, [Sequential] to allocate consecutive ports
+//! or [Ephemeral] to let the operating system pick free ports.
+
+use crate::instance::WildFlyInstance;
+use crate::{try_identifier, WildFlyContainer, DEV_SLOTS, VERSIONS, WILDFLY_DEV};
+use anyhow::{anyhow, bail, Result};
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// The default bases of the host ports: one block of 1000 ports per socket binding
+pub const DEFAULT_BASES: Ports = Ports {
+    http: 8000,
+    management: 9000,
+    https: 10000,
+    debug: 11000,
+    ajp: 12000,
+    management_https: 13000,
+};
+
+/// The names of the socket bindings in the order of [Ports::to_array]
+pub const BINDINGS: [&str; 6] = [
+    "http",
+    "management",
+    "https",
+    "debug",
+    "ajp",
+    "management-https",
+];
+
+/// The number of instances of dev builds and versions up to 99.9 for [VersionBased].
+/// The bands of the following instances belong to versions after 99.9.
+pub const MAX_INSTANCES: u16 = 7;
+
+/// The band of versions with a two-digit minor from x.10 to x.19
+const TWO_DIGIT_MINOR_BAND: u32 = 7;
+
+/// The band of versions with a three-digit major from 100.0 to 199.9
+const THREE_DIGIT_MAJOR_BAND: u32 = 8;
+
+/// The size of the port offsets inside a band
+const BAND_SLOTS: u32 = 1000;
+
+/// The ports used inside the container
+pub const CONTAINER_PORTS: Ports = Ports {
+    http: 8080,
+    management: 9990,
+    https: 8443,
+    debug: 8787,
+    ajp: 8009,
+    management_https: 9993,
+};
+
+/// The host ports of a [WildFlyContainer], one per socket binding
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ports {
+    /// The HTTP port (mapped to 8080 inside the container)
+    pub http: u16,
+
+    /// The management port (mapped to 9990 inside the container)
+    pub management: u16,
+
+    /// The HTTPS port (mapped to 8443 inside the container)
+    pub https: u16,
+
+    /// The JPDA debug port (mapped to 8787 inside the container)
+    pub debug: u16,
+
+    /// The AJP port (mapped to 8009 inside the container)
+    pub ajp: u16,
+
+    /// The management HTTPS port (mapped to 9993 inside the container)
+    pub management_https: u16,
+}
+
+/// Maps a host port to a port inside the container
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PortMapping {
+    /// The name of the socket binding like "http" or "management-https"
+    pub name: &'static str,
+
+    /// The port on the host
+    pub host: u16,
+
+    /// The port inside the container
+    pub container: u16,
+}
+
+impl Ports {
+    /// Returns the ports in a fixed order: http, management, https, debug, ajp, management-https.
+    pub fn to_array(&self) -> [u16; 6] {
+        [
+            self.http,
+            self.management,
+            self.https,
+            self.debug,
+            self.ajp,
+            self.management_https,
+        ]
+    }
+
+    /// Returns the mappings from these host ports to the [CONTAINER_PORTS].
+    pub fn mappings(&self) -> Vec<PortMapping> {
+        BINDINGS
+            .into_iter()
+            .zip(self.to_array())
+            .zip(CONTAINER_PORTS.to_array())
+            .map(|((name, host), container)| PortMapping {
+                name,
+                host,
+                container,
+            })
+            .collect()
+    }
+
+    fn from_array(ports: [u16; 6]) -> Self {
+        Self {
+            http: ports[0],
+            management: ports[1],
+            https: ports[2],
+            debug: ports[3],
+            ajp: ports[4],
+            management_https: ports[5],
+        }
+    }
+
+    fn checked_add(&self, offset: u32) -> Option<Ports> {
+        let offset = u16::try_from(offset).ok()?;
+        let mut ports = self.to_array();
+        for port in ports.iter_mut() {
+            *port = port.checked_add(offset)?;
+        }
+        Some(Ports::from_array(ports))
+    }
+}
+
+/// A host port resolved to the instance and socket binding it belongs to
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ResolvedPort {
+    /// The instance
+    pub instance: WildFlyInstance,
+
+    /// The socket binding, host and container port
+    pub mapping: PortMapping,
+}
+
+/// Allocates host ports for [WildFlyContainer]s.
+pub trait PortStrategy {
+    /// Returns the ports for the given container or an error if no ports can be allocated.
+    fn ports(&self, wildfly: &WildFlyContainer) -> Result<Ports>;
+
+    /// Returns the ports for the given instance.
+    ///
+    /// The default implementation ignores the instance index and delegates to
+    /// [PortStrategy::ports]. Strategies which return the same ports for the same
+    /// container must override this method to keep instances apart.
+    fn instance_ports(&self, instance: &WildFlyInstance) -> Result<Ports> {
+        self.ports(&instance.wildfly)
+    }
+
+    /// Resolves a host port to all instances and socket bindings which use this port.
+    ///
+    /// The default implementation returns an empty vector: Only strategies which derive
+    /// the ports from the version can be inverted.
+    fn resolve(&self, _port: u16) -> Vec<ResolvedPort> {
+        vec![]
+    }
+}
+
+/// Adds the port offset of a container to configurable bases.
+///
+/// The port offset is derived from the version: 261 for 26.1, the slot (0, 1, ...) for dev builds.
+/// Instances add `index * shift` on top, where the shift is the span of all bases
+/// plus 1000 (6000 for the [DEFAULT_BASES]). So the second instance of 26.1 uses
+/// 14261 and 15261 for HTTP and management.
+///
+/// The ports of one shift form a band. Dev builds and versions up to 99.9 use the bands
+/// of up to [MAX_INSTANCES] instances. Versions after 99.9 run in one instance only:
+/// x.10 to x.19 in band 7 with the offset `<x><minor % 10>` (26.10 uses 50260 for HTTP),
+/// 100.0 to 199.9 in band 8 with the offset `<major % 100><minor>` (100.0 uses 56000).
+/// With the default bases, this keeps all ports distinct. Other versions have no ports.
+///
+/// [PortStrategy::resolve] maps offsets below [DEV_SLOTS] to the default development
+/// build in that slot (8001 → "dev1"), since the ports don't tell the sources apart.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct VersionBased {
+    bases: Ports,
+}
+
+impl VersionBased {
+    /// Uses the given bases instead of the [DEFAULT_BASES].
+    ///
+    /// Fails if two bases are less than 1000 ports apart, since the port offsets of
+    /// one base would run into the next one.
+    pub fn with_bases(bases: Ports) -> Result<Self> {
+        let span = span(&bases);
+        if span < BAND_SLOTS {
+            bail!(
+                "the bases {:?} must be at least {} ports apart, not {}",
+                bases.to_array(),
+                BAND_SLOTS,
+                span
+            )
+        }
+        Ok(Self { bases })
+    }
+
+    /// Returns the offset added for each instance index.
+    pub fn instance_shift(&self) -> u32 {
+        let ports = self.bases.to_array();
+        let min = ports.iter().min().copied().unwrap_or_default() as u32;
+        let max = ports.iter().max().copied().unwrap_or_default() as u32;
+        max - min + 1000
+    }
+
+    fn ports_with_offset(&self, wildfly: &WildFlyContainer, index: u16) -> Result<Ports> {
+        let Some(band) = band(wildfly) else {
+            bail!("no ports for {}", wildfly.display_version())
+        };
+        let instances = if band == 0 { MAX_INSTANCES } else { 1 };
+        if index >= instances {
+            bail!(
+                "no ports for instance {} of {}",
+                index,
+                wildfly.display_version()
+            )
+        }
+        (band + index as u32)
+            .checked_mul(self.instance_shift())
+            .and_then(|shift| shift.checked_add(wildfly.port_offset))
+            .and_then(|offset| self.bases.checked_add(offset))
+            .ok_or_else(|| anyhow!("port overflow for {}", wildfly.display_version()))
+    }
+}
+
+impl Default for VersionBased {
+    fn default() -> Self {
+        Self {
+            bases: DEFAULT_BASES,
+        }
+    }
+}
+
+impl PortStrategy for VersionBased {
+    fn ports(&self, wildfly: &WildFlyContainer) -> Result<Ports> {
+        self.ports_with_offset(wildfly, 0)
+    }
+
+    fn instance_ports(&self, instance: &WildFlyInstance) -> Result<Ports> {
+        self.ports_with_offset(&instance.wildfly, instance.index)
+    }
+
+    fn resolve(&self, port: u16) -> Vec<ResolvedPort> {
+        let shift = self.instance_shift();
+        let mut resolved = vec![];
+        for base in self.bases.to_array() {
+            let Some(relative) = port.checked_sub(base) else {
+                continue;
+            };
+            let band = relative as u32 / shift;
+            let offset = relative as u32 % shift;
+            if offset >= BAND_SLOTS {
+                continue;
+            }
+            let (major, minor) = (offset / 10, offset % 10);
+            let (wildfly, index) = match band {
+                TWO_DIGIT_MINOR_BAND => (lookup(major, 10 + minor), 0),
+                THREE_DIGIT_MAJOR_BAND => (lookup(100 + major, minor), 0),
+                _ if offset < DEV_SLOTS => (WILDFLY_DEV.clone().with_dev_slot(offset).ok(), band),
+                _ => (VERSIONS.get(&offset).cloned(), band),
+            };
+            if let Some(wildfly) = wildfly {
+                let instance = WildFlyInstance::new(wildfly, index as u16);
+                if let Ok(ports) = self.instance_ports(&instance) {
+                    resolved.extend(
+                        ports
+                            .mappings()
+                            .into_iter()
+                            .filter(|mapping| mapping.host == port)
+                            .map(|mapping| ResolvedPort {
+                                instance: instance.clone(),
+                                mapping,
+                            }),
+                    );
+                }
+            }
+        }
+        resolved
+    }
+}
+
+/// Returns the band of the ports of the container (see [VersionBased]).
+fn band(wildfly: &WildFlyContainer) -> Option<u32> {
+    let (major, minor) = (wildfly.version.major, wildfly.version.minor);
+    if wildfly.port_offset == wildfly.identifier {
+        Some(0)
+    } else if major < 100 && (10..20).contains(&minor) {
+        Some(TWO_DIGIT_MINOR_BAND)
+    } else if (100..200).contains(&major) && minor < 10 {
+        Some(THREE_DIGIT_MAJOR_BAND)
+    } else {
+        None
+    }
+}
+
+/// Returns the smallest distance between two bases.
+fn span(bases: &Ports) -> u32 {
+    let mut ports = bases.to_array();
+    ports.sort_unstable();
+    ports
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]) as u32)
+        .min()
+        .unwrap_or_default()
+}
+
+fn lookup(major: u32, minor: u32) -> Option<WildFlyContainer> {
+    try_identifier(major, minor).and_then(|identifier| VERSIONS.get(&identifier).cloned())
+}
+
+/// Hands out consecutive ports starting at the configured bases, one offset per call.
+///
+/// Asking twice for the same container yields different ports. The offset is shared by
+/// all bases, so allocation fails once it reaches the smallest distance between two bases
+/// (after 1000 calls with the [DEFAULT_BASES]).
+#[derive(Debug)]
+pub struct Sequential {
+    bases: Ports,
+    next: AtomicU32,
+}
+
+impl Sequential {
+    /// Starts allocating at the given bases.
+    pub fn new(bases: Ports) -> Self {
+        Self {
+            bases,
+            next: AtomicU32::new(0),
+        }
+    }
+}
+
+impl Default for Sequential {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASES)
+    }
+}
+
+impl PortStrategy for Sequential {
+    fn ports(&self, _wildfly: &WildFlyContainer) -> Result<Ports> {
+        let offset = self.next.fetch_add(1, Ordering::SeqCst);
+        if offset >= span(&self.bases) {
+            bail!("no more ports after offset {}", offset)
+        }
+        self.bases
+            .checked_add(offset)
+            .ok_or_else(|| anyhow!("no more ports after offset {}", offset))
+    }
+}
+
+/// Lets the operating system choose free ports on localhost.
+///
+/// The ports are free at the time of the call, but are not reserved afterward.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ephemeral;
+
+impl PortStrategy for Ephemeral {
+    fn ports(&self, _wildfly: &WildFlyContainer) -> Result<Ports> {
+        // keep all listeners open, so that the OS returns different ports
+        let listeners = (0..6)
+            .map(|_| TcpListener::bind((Ipv4Addr::LOCALHOST, 0)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut ports = [0; 6];
+        for (port, listener) in ports.iter_mut().zip(&listeners) {
+            *port = listener.local_addr()?.port();
+        }
+        Ok(Ports::from_array(ports))
+    }
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod ports_tests {
+    use crate::instance::WildFlyInstance;
+    use crate::ports::{
+        Ephemeral, PortStrategy, Ports, Sequential, VersionBased, DEFAULT_BASES, MAX_INSTANCES,
+    };
+    use crate::WildFlyContainer;
+    use semver::Version;
+    use std::collections::HashSet;
+
+    #[test]
+    fn version_based() {
+        let wf = WildFlyContainer::version("26.1").unwrap();
+        let ports = VersionBased::default().ports(&wf).unwrap();
+        assert_eq!(
+            ports,
+            Ports {
+                http: 8261,
+                management: 9261,
+                https: 10261,
+                debug: 11261,
+                ajp: 12261,
+                management_https: 13261,
+            }
+        );
+        let dev = WildFlyContainer::version("dev").unwrap();
+        let ports = VersionBased::default().ports(&dev).unwrap();
+        assert_eq!(ports, DEFAULT_BASES);
+    }
+
+    #[test]
+    fn version_based_with_bases() {
+        let wf = WildFlyContainer::version("39").unwrap();
+        let strategy = VersionBased::with_bases(Ports {
+            http: 18000,
+            management: 19000,
+            https: 20000,
+            debug: 21000,
+            ajp: 22000,
+            management_https: 23000,
+        })
+        .unwrap();
+        let ports = strategy.ports(&wf).unwrap();
+        assert_eq!((ports.http, ports.management), (18390, 19390));
+        assert_eq!(ports.management_https, 23390);
+        assert_eq!(strategy.instance_shift(), 6000);
+    }
+
+    #[test]
+    fn version_based_overflow() {
+        let wf = WildFlyContainer::version("39").unwrap();
+        let bases = Ports {
+            management_https: 65500,
+            ..DEFAULT_BASES
+        };
+        assert!(VersionBased::with_bases(bases).unwrap().ports(&wf).is_err());
+    }
+
+    #[test]
+    fn version_based_close_bases() {
+        let close = Ports {
+            management: 8500,
+            ..DEFAULT_BASES
+        };
+        assert!(VersionBased::with_bases(close).is_err());
+        let same = Ports {
+            debug: 10000,
+            ..DEFAULT_BASES
+        };
+        assert!(VersionBased::with_bases(same).is_err());
+        assert_eq!(
+            VersionBased::default(),
+            VersionBased::with_bases(DEFAULT_BASES).unwrap()
+        );
+    }
+
+    #[test]
+    fn version_based_extended() {
+        let wf = |major, minor| {
+            WildFlyContainer::new(
+                Version::new(major, minor, 0),
+                Version::new(0, 0, 0),
+                "",
+                "",
+                vec![],
+            )
+        };
+        let strategy = VersionBased::default();
+        let ports = strategy.ports(&wf(26, 10)).unwrap();
+        assert_eq!((ports.http, ports.management), (50260, 51260));
+        let ports = strategy.ports(&wf(100, 0)).unwrap();
+        assert_eq!((ports.http, ports.management_https), (56000, 61000));
+        assert_eq!(61999, strategy.ports(&wf(199, 9)).unwrap().management_https);
+
+        let mut containers = vec![wf(26, 10), wf(27, 10), wf(100, 0), wf(126, 0)];
+        containers.extend(WildFlyContainer::enumeration("..,dev").unwrap());
+        let mut ports = HashSet::new();
+        for wildfly in &containers {
+            for index in 0..MAX_INSTANCES {
+                let instance = WildFlyInstance::new(wildfly.clone(), index);
+                match strategy.instance_ports(&instance) {
+                    Ok(instance_ports) => {
+                        for port in instance_ports.to_array() {
+                            assert!(ports.insert(port), "{} used twice", port);
+                        }
+                    }
+                    Err(_) => assert!(wildfly.identifier >= 1000 && index > 0),
+                }
+            }
+        }
+
+        assert!(strategy.ports(&wf(26, 20)).is_err());
+        assert!(strategy.ports(&wf(200, 0)).is_err());
+        let instance = WildFlyInstance::new(wf(26, 10), 1);
+        assert!(strategy.instance_ports(&instance).is_err());
+        let instance = WildFlyInstance::new(wf(26, 1), MAX_INSTANCES);
+        assert!(strategy.instance_ports(&instance).is_err());
+    }
+
+    #[test]
+    fn mappings() {
+        let wf = WildFlyContainer::version("26.1").unwrap();
+        let mappings = VersionBased::default().ports(&wf).unwrap().mappings();
+        assert_eq!(6, mappings.len());
+        assert_eq!(
+            ("http", 8261, 8080),
+            (mappings[0].name, mappings[0].host, mappings[0].container)
+        );
+        assert_eq!(
+            ("management-https", 13261, 9993),
+            (mappings[5].name, mappings[5].host, mappings[5].container)
+        );
+    }
+
+    #[test]
+    fn sequential() {
+        let wf = WildFlyContainer::version("26.1").unwrap();
+        let strategy = Sequential::default();
+        let first = strategy.ports(&wf).unwrap();
+        let second = strategy.ports(&wf).unwrap();
+        assert_eq!(first, DEFAULT_BASES);
+        assert_eq!((second.http, second.management), (8001, 9001));
+    }
+
+    #[test]
+    fn sequential_exhausted() {
+        let wf = WildFlyContainer::version("26.1").unwrap();
+        let strategy = Sequential::new(Ports {
+            ajp: 65535,
+            ..DEFAULT_BASES
+        });
+        assert!(strategy.ports(&wf).is_ok());
+        assert!(strategy.ports(&wf).is_err());
+    }
+
+    #[test]
+    fn sequential_span() {
+        let wf = WildFlyContainer::version("26.1").unwrap();
+        let strategy = Sequential::new(Ports {
+            management: 8002,
+            ..DEFAULT_BASES
+        });
+        let first = strategy.ports(&wf).unwrap();
+        let second = strategy.ports(&wf).unwrap();
+        assert_eq!((first.http, second.http), (8000, 8001));
+        assert!(strategy.ports(&wf).is_err());
+
+        let strategy = Sequential::default();
+        for _ in 0..1000 {
+            assert!(strategy.ports(&wf).unwrap().http < DEFAULT_BASES.management);
+        }
+        assert!(strategy.ports(&wf).is_err());
+    }
+
+    #[test]
+    fn ephemeral() {
+        let wf = WildFlyContainer::version("26.1").unwrap();
+        let ports = Ephemeral.ports(&wf).unwrap().to_array();
+        assert!(ports.iter().all(|port| *port != 0));
+        assert_eq!(6, ports.iter().collect::<HashSet<_>>().len());
+    }
+
+    #[test]
+    fn resolve() {
+        let strategy = VersionBased::default();
+        let resolved = strategy.resolve(8261);
+        assert_eq!(1, resolved.len());
+        assert_eq!(261, resolved[0].instance.wildfly.identifier);
+        assert_eq!(0, resolved[0].instance.index);
+        assert_eq!("http", resolved[0].mapping.name);
+
+        let resolved = strategy.resolve(15390);
+        assert_eq!(1, resolved.len());
+        assert_eq!(390, resolved[0].instance.wildfly.identifier);
+        assert_eq!(1, resolved[0].instance.index);
+        assert_eq!("management", resolved[0].mapping.name);
+
+        let resolved = strategy.resolve(9000);
+        assert_eq!(1, resolved.len());
+        assert!(resolved[0].instance.wildfly.is_dev());
+    }
+
+    #[test]
+    fn resolve_dev_slots() {
+        let strategy = VersionBased::default();
+        let resolved = strategy.resolve(8001);
+        assert_eq!(1, resolved.len());
+        assert_eq!("dev1", resolved[0].instance.wildfly.display_version());
+        assert_eq!(0, resolved[0].instance.index);
+        assert_eq!("http", resolved[0].mapping.name);
+
+        let resolved = strategy.resolve(15099);
+        assert_eq!(1, resolved.len());
+        assert_eq!("dev99", resolved[0].instance.wildfly.display_version());
+        assert_eq!(1, resolved[0].instance.index);
+        assert_eq!("management", resolved[0].mapping.name);
+    }
+
+    #[test]
+    fn resolve_roundtrip() {
+        let strategy = VersionBased::default();
+        for instance in crate::instance::WildFlyInstance::enumeration("3x..").unwrap() {
+            for mapping in strategy.instance_ports(&instance).unwrap().mappings() {
+                let resolved = strategy.resolve(mapping.host);
+                assert_eq!(1, resolved.len());
+                assert_eq!(instance, resolved[0].instance);
+                assert_eq!(mapping, resolved[0].mapping);
+            }
+        }
+    }
+
+    #[test]
+    fn resolve_unknown() {
+        let strategy = VersionBased::default();
+        assert!(strategy.resolve(80).is_empty());
+        assert!(strategy.resolve(8105).is_empty());
+        assert!(strategy.resolve(9999).is_empty());
+        assert!(Sequential::default().resolve(8261).is_empty());
+    }
+}
@@ -0,0 +1,348 @@
+//! A minimal client for the OCI distribution API.
+//!
+//! [Registry] talks to registries anonymously. Registries which hand out tokens for anonymous
+//! pulls (like Docker Hub) answer with a `WWW-Authenticate: Bearer` challenge. The client
+//! fetches a token from the announced realm and keeps it for the repository.
+//!
+//! [Registry::manifest] reads the platforms an image advertises: from the entries of an image
+//! index or, for single-platform images, from the image configuration.
+//!
+//! The endpoint of a registry can be replaced with [Registry::with_endpoint], e.g. to talk
+//! to a local registry stand-in.
+
+use crate::image::{ImageReference, DOCKER_HUB};
+use anyhow::{anyhow, bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The media types accepted for manifests: image indexes first, then single manifests
+const MANIFEST_TYPES: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.docker.distribution.manifest.v2+json";
+
+lazy_static! {
+    static ref CHALLENGE_RE: Regex = Regex::new(r#"(?<key>[a-z]+)="(?<value>[^"]*)""#).unwrap();
+    static ref NEXT_RE: Regex = Regex::new(r#"<(?<url>[^>]+)>\s*;\s*rel="?next"?"#).unwrap();
+}
+
+/// The platforms of an image manifest
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Manifest {
+    /// The digest of the manifest (header `Docker-Content-Digest`), if the registry sent it
+    pub digest: Option<String>,
+
+    /// The advertised platforms like "linux/amd64" or "linux/arm64/v8"
+    pub platforms: Vec<String>,
+
+    /// The digests of the platform manifests by platform (image indexes only)
+    pub digests: BTreeMap<String, String>,
+}
+
+/// A client for the OCI distribution API
+pub struct Registry {
+    agent: ureq::Agent,
+    endpoints: BTreeMap<String, String>,
+    tokens: Mutex<BTreeMap<String, String>>,
+}
+
+impl Registry {
+    /// Creates a client. `timeout` applies to each request.
+    pub fn new(timeout: Duration) -> Self {
+        Registry {
+            agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+            endpoints: BTreeMap::new(),
+            tokens: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Sends the requests for the given registry like "quay.io" to the given URL
+    /// like "http://localhost:5000".
+    pub fn with_endpoint(mut self, registry: &str, url: &str) -> Self {
+        self.endpoints
+            .insert(registry.to_string(), url.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Returns the URL of the given registry.
+    pub fn endpoint(&self, registry: &str) -> String {
+        match self.endpoints.get(registry) {
+            Some(url) => url.clone(),
+            None if registry == DOCKER_HUB => "https://registry-1.docker.io".to_string(),
+            None => format!("https://{}", registry),
+        }
+    }
+
+    /// Returns all tags of the repository of the image (`GET /v2/<name>/tags/list`).
+    ///
+    /// Follows the `Link` headers of paginated responses.
+    pub fn tags(&self, image: &ImageReference) -> Result<Vec<String>> {
+        let endpoint = self.endpoint(&image.registry);
+        let mut url = format!("{}/v2/{}/tags/list", endpoint, image.path());
+        let mut tags = vec![];
+        loop {
+            let response = self.request("GET", image, &url, "application/json")?;
+            let next = response
+                .header("Link")
+                .and_then(|link| NEXT_RE.captures(link))
+                .map(|c| c["url"].to_string());
+            let json: Value = serde_json::from_str(&response.into_string()?)?;
+            if let Some(names) = json.get("tags").and_then(Value::as_array) {
+                tags.extend(names.iter().filter_map(Value::as_str).map(str::to_string));
+            }
+            match next {
+                Some(next) if next.starts_with('/') => url = format!("{}{}", endpoint, next),
+                Some(next) => url = next,
+                None => break,
+            }
+        }
+        Ok(tags)
+    }
+
+    /// Returns the manifest of the image (`GET /v2/<name>/manifests/<reference>`)
+    /// or `None` if the registry doesn't know the image.
+    ///
+    /// The reference is the digest of the image, its tag or "latest".
+    pub fn manifest(&self, image: &ImageReference) -> Result<Option<Manifest>> {
+        let endpoint = self.endpoint(&image.registry);
+        let reference = image
+            .digest
+            .as_deref()
+            .or(image.tag.as_deref())
+            .unwrap_or("latest");
+        let url = format!("{}/v2/{}/manifests/{}", endpoint, image.path(), reference);
+        let response = match self.request("GET", image, &url, MANIFEST_TYPES) {
+            Ok(response) => response,
+            Err(e) if not_found(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let digest = response.header("Docker-Content-Digest").map(str::to_string);
+        let json: Value = serde_json::from_str(&response.into_string()?)?;
+        let mut manifest = Manifest {
+            digest,
+            ..Manifest::default()
+        };
+        if let Some(entries) = json.get("manifests").and_then(Value::as_array) {
+            for entry in entries {
+                // skip attestations and other artifacts
+                let Some(platform) = entry.get("platform").and_then(platform) else {
+                    continue;
+                };
+                if let Some(digest) = entry.get("digest").and_then(Value::as_str) {
+                    manifest
+                        .digests
+                        .insert(platform.clone(), digest.to_string());
+                }
+                manifest.platforms.push(platform);
+            }
+        } else if let Some(config) = json.pointer("/config/digest").and_then(Value::as_str) {
+            let url = format!("{}/v2/{}/blobs/{}", endpoint, image.path(), config);
+            let response = self.request("GET", image, &url, "application/json")?;
+            let json: Value = serde_json::from_str(&response.into_string()?)?;
+            if let Some(platform) = platform(&json) {
+                if let Some(digest) = &manifest.digest {
+                    manifest.digests.insert(platform.clone(), digest.clone());
+                }
+                manifest.platforms.push(platform);
+            }
+        } else {
+            bail!("unknown manifest of {}", image)
+        }
+        Ok(Some(manifest))
+    }
+
+    /// Sends a request for a resource of the image's repository.
+    pub(crate) fn request(
+        &self,
+        method: &str,
+        image: &ImageReference,
+        url: &str,
+        accept: &str,
+    ) -> Result<ureq::Response> {
+        let repository = image.repository();
+        let token = self
+            .tokens
+            .lock()
+            .ok()
+            .and_then(|tokens| tokens.get(&repository).cloned());
+        let request = |token: Option<&str>| {
+            let request = self.agent.request(method, url).set("Accept", accept);
+            match token {
+                Some(token) => request.set("Authorization", &format!("Bearer {}", token)),
+                None => request,
+            }
+        };
+        match request(token.as_deref()).call() {
+            Err(ureq::Error::Status(401, response)) if token.is_none() => {
+                let challenge = response.header("WWW-Authenticate").unwrap_or_default();
+                let token = self.token(challenge)?;
+                if let Ok(mut tokens) = self.tokens.lock() {
+                    tokens.insert(repository, token.clone());
+                }
+                Ok(request(Some(&token)).call()?)
+            }
+            result => Ok(result?),
+        }
+    }
+
+    fn token(&self, challenge: &str) -> Result<String> {
+        let Some(parameters) = challenge.strip_prefix("Bearer ") else {
+            bail!("unsupported authentication '{}'", challenge)
+        };
+        let parameters = CHALLENGE_RE
+            .captures_iter(parameters)
+            .map(|c| (c["key"].to_string(), c["value"].to_string()))
+            .collect::<BTreeMap<_, _>>();
+        let Some(realm) = parameters.get("realm") else {
+            bail!("no realm in authentication '{}'", challenge)
+        };
+        let mut request = self.agent.get(realm);
+        for key in ["service", "scope"] {
+            if let Some(value) = parameters.get(key) {
+                request = request.query(key, value);
+            }
+        }
+        let json: Value = serde_json::from_str(&request.call()?.into_string()?)?;
+        json.get("token")
+            .or_else(|| json.get("access_token"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("no token from {}", realm))
+    }
+}
+
+fn platform(json: &Value) -> Option<String> {
+    let os = json.get("os").and_then(Value::as_str)?;
+    let architecture = json.get("architecture").and_then(Value::as_str)?;
+    if os == "unknown" || architecture == "unknown" {
+        return None;
+    }
+    Some(match json.get("variant").and_then(Value::as_str) {
+        Some(variant) => format!("{}/{}/{}", os, architecture, variant),
+        None => format!("{}/{}", os, architecture),
+    })
+}
+
+fn not_found(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ureq::Error>(),
+        Some(ureq::Error::Status(404, _))
+    )
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod registry_tests {
+    use crate::image::ImageReference;
+    use crate::registry::Registry;
+    use crate::testing::{bind, not_found, ok, ok_with, serve, stub, TIMEOUT};
+
+    #[test]
+    fn endpoint() {
+        let registry = Registry::new(TIMEOUT).with_endpoint("quay.io", "http://localhost:5000/");
+        assert_eq!("http://localhost:5000", registry.endpoint("quay.io"));
+        assert_eq!(
+            "https://registry-1.docker.io",
+            registry.endpoint("docker.io")
+        );
+        assert_eq!("https://ghcr.io", registry.endpoint("ghcr.io"));
+    }
+
+    #[test]
+    fn paginated_tags() {
+        let url = stub(|request| {
+            if request.starts_with("GET /v2/wildfly/wildfly/tags/list?n=2&last=b ") {
+                ok(r#"{"name":"wildfly/wildfly","tags":["c"]}"#)
+            } else if request.starts_with("GET /v2/wildfly/wildfly/tags/list ") {
+                ok_with(
+                    vec![(
+                        "Link",
+                        r#"</v2/wildfly/wildfly/tags/list?n=2&last=b>; rel="next""#.to_string(),
+                    )],
+                    r#"{"name":"wildfly/wildfly","tags":["a","b"]}"#,
+                )
+            } else {
+                not_found()
+            }
+        });
+        let registry = Registry::new(TIMEOUT).with_endpoint("quay.io", &url);
+        let image = ImageReference::parse("quay.io/wildfly/wildfly").unwrap();
+        assert_eq!(vec!["a", "b", "c"], registry.tags(&image).unwrap());
+        let image = ImageReference::parse("quay.io/wildfly/unknown").unwrap();
+        assert!(registry.tags(&image).is_err());
+    }
+
+    #[test]
+    fn token() {
+        let (listener, url) = bind();
+        let challenge = format!(
+            r#"Bearer realm="{}/token",service="registry",scope="repository:jboss/wildfly:pull""#,
+            url
+        );
+        serve(listener, move |request| {
+            if request.starts_with(
+                "GET /token?service=registry&scope=repository%3Ajboss%2Fwildfly%3Apull ",
+            ) {
+                ok(r#"{"token":"secret"}"#)
+            } else if request.contains("Bearer secret") {
+                ok(r#"{"name":"jboss/wildfly","tags":["20.0.1.Final"]}"#)
+            } else {
+                (
+                    "401 Unauthorized",
+                    vec![("WWW-Authenticate".to_string(), challenge.clone())],
+                    String::new(),
+                )
+            }
+        });
+        let registry = Registry::new(TIMEOUT).with_endpoint("docker.io", &url);
+        let image = ImageReference::parse("jboss/wildfly").unwrap();
+        assert_eq!(vec!["20.0.1.Final"], registry.tags(&image).unwrap());
+        assert!(registry.token(r#"Basic realm="x""#).is_err());
+    }
+
+    #[test]
+    fn manifest() {
+        let url = stub(|request| {
+            if request.starts_with("GET /v2/wildfly/wildfly/manifests/39.0.1.Final-2-jdk21 ") {
+                ok_with(
+                    vec![("Docker-Content-Digest", "sha256:index".to_string())],
+                    r#"{"mediaType":"application/vnd.oci.image.index.v1+json","manifests":[
+                        {"digest":"sha256:amd64","platform":{"os":"linux","architecture":"amd64"}},
+                        {"digest":"sha256:arm64","platform":{"os":"linux","architecture":"arm64","variant":"v8"}},
+                        {"digest":"sha256:attestation","platform":{"os":"unknown","architecture":"unknown"}}]}"#,
+                )
+            } else if request.starts_with("GET /v2/jboss/wildfly/manifests/20.0.1.Final ") {
+                ok_with(
+                    vec![("Docker-Content-Digest", "sha256:single".to_string())],
+                    r#"{"config":{"digest":"sha256:config"},"layers":[]}"#,
+                )
+            } else if request.starts_with("GET /v2/jboss/wildfly/blobs/sha256:config ") {
+                ok(r#"{"architecture":"amd64","os":"linux","config":{}}"#)
+            } else {
+                not_found()
+            }
+        });
+        let registry = Registry::new(TIMEOUT)
+            .with_endpoint("quay.io", &url)
+            .with_endpoint("docker.io", &url);
+
+        let image = ImageReference::parse("quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21").unwrap();
+        let manifest = registry.manifest(&image).unwrap().unwrap();
+        assert_eq!(Some("sha256:index".to_string()), manifest.digest);
+        assert_eq!(vec!["linux/amd64", "linux/arm64/v8"], manifest.platforms);
+        assert_eq!("sha256:arm64", manifest.digests["linux/arm64/v8"]);
+
+        let image = ImageReference::parse("jboss/wildfly:20.0.1.Final").unwrap();
+        let manifest = registry.manifest(&image).unwrap().unwrap();
+        assert_eq!(vec!["linux/amd64"], manifest.platforms);
+        assert_eq!("sha256:single", manifest.digests["linux/amd64"]);
+
+        let image = ImageReference::parse("jboss/wildfly:20.0.2.Final").unwrap();
+        assert_eq!(None, registry.manifest(&image).unwrap());
+    }
+}
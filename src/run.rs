@@ -0,0 +1,240 @@
+//! Generates `podman run` and `docker run` commands for [WildFlyInstance]s.
+//!
+//! [run_specs] turns a resolved enumeration into [RunSpec]s, which render
+//! themselves as argument vectors for the chosen [Engine]:
+//!
+//! ```text
+//! podman run --name wildfly-26.1 -p 8261:8080 -p 9261:9990 quay.io/wildfly/wildfly:26.1.3.Final-jdk17
+//!     /opt/jboss/wildfly/bin/standalone.sh -b 0.0.0.0 -bmanagement 0.0.0.0
+//! ```
+
+use crate::instance::WildFlyInstance;
+use crate::mirror;
+use crate::ports::{PortMapping, PortStrategy, Ports};
+use anyhow::{bail, Result};
+
+/// The WildFly installation directory used by the `docker.io/jboss/wildfly`
+/// and `quay.io/wildfly/wildfly` images
+pub const WILDFLY_HOME: &str = "/opt/jboss/wildfly";
+
+/// The socket bindings published by default
+pub const DEFAULT_BINDINGS: [&str; 2] = ["http", "management"];
+
+/// The container engine
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Engine {
+    Podman,
+    Docker,
+}
+
+impl Engine {
+    /// Returns the name of the executable.
+    pub fn command(&self) -> &'static str {
+        match self {
+            Engine::Podman => "podman",
+            Engine::Docker => "docker",
+        }
+    }
+}
+
+/// Everything needed to run one [WildFlyInstance]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RunSpec {
+    /// The container name, unique across the enumeration
+    pub name: String,
+
+    /// The container image
+    pub image: String,
+
+    /// The host ports of all socket bindings
+    pub ports: Ports,
+
+    /// The names of the published socket bindings (see [crate::ports::BINDINGS])
+    pub published: Vec<&'static str>,
+
+    /// An optional platform like "linux/arm64"
+    pub platform: Option<String>,
+
+    /// Environment variables
+    pub env: Vec<(String, String)>,
+
+    /// Extra arguments for the engine, added before the image
+    pub args: Vec<String>,
+
+    /// The command run inside the container, added after the image
+    pub command: Vec<String>,
+}
+
+impl RunSpec {
+    /// Creates a run specification which publishes the HTTP and management port
+    /// and starts WildFly bound to all interfaces.
+    ///
+    /// Fails for dev builds and local distributions, which have no image,
+    /// and if the strategy fails to allocate ports.
+    pub fn new(instance: &WildFlyInstance, strategy: &dyn PortStrategy) -> Result<RunSpec> {
+        if !instance.wildfly.has_image() {
+            bail!("no image for {}", instance.name())
+        }
+        mirror::validate()?;
+        Ok(RunSpec {
+            name: instance.name(),
+            image: instance.wildfly.image_name(),
+            ports: instance.ports(strategy)?,
+            published: DEFAULT_BINDINGS.to_vec(),
+            platform: None,
+            env: vec![],
+            args: vec![],
+            command: standalone_command(),
+        })
+    }
+
+    /// Returns the mappings of the published socket bindings.
+    pub fn mappings(&self) -> Vec<PortMapping> {
+        self.ports
+            .mappings()
+            .into_iter()
+            .filter(|mapping| self.published.contains(&mapping.name))
+            .collect()
+    }
+
+    /// Renders the command as argument vector, starting with the engine executable.
+    pub fn argv(&self, engine: Engine) -> Vec<String> {
+        let mut argv = vec![
+            engine.command().to_string(),
+            "run".to_string(),
+            "--name".to_string(),
+            self.name.clone(),
+        ];
+        if let Some(platform) = &self.platform {
+            argv.push("--platform".to_string());
+            argv.push(platform.clone());
+        }
+        for mapping in self.mappings() {
+            argv.push("-p".to_string());
+            argv.push(format!("{}:{}", mapping.host, mapping.container));
+        }
+        for (key, value) in &self.env {
+            argv.push("-e".to_string());
+            argv.push(format!("{}={}", key, value));
+        }
+        argv.extend(self.args.iter().cloned());
+        argv.push(self.image.clone());
+        argv.extend(self.command.iter().cloned());
+        argv
+    }
+}
+
+/// Returns the command which starts WildFly with the public and management
+/// interfaces bound to all addresses.
+pub fn standalone_command() -> Vec<String> {
+    vec![
+        format!("{}/bin/standalone.sh", WILDFLY_HOME),
+        "-b".to_string(),
+        "0.0.0.0".to_string(),
+        "-bmanagement".to_string(),
+        "0.0.0.0".to_string(),
+    ]
+}
+
+/// Turns the given instances into [RunSpec]s using [RunSpec::new].
+pub fn run_specs(
+    instances: &[WildFlyInstance],
+    strategy: &dyn PortStrategy,
+) -> Result<Vec<RunSpec>> {
+    instances
+        .iter()
+        .map(|instance| RunSpec::new(instance, strategy))
+        .collect()
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod run_tests {
+    use crate::instance::WildFlyInstance;
+    use crate::ports::{VersionBased, BINDINGS};
+    use crate::run::{run_specs, Engine, RunSpec};
+
+    #[test]
+    fn podman() {
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        let spec = RunSpec::new(&instances[0], &VersionBased::default()).unwrap();
+        assert_eq!(
+            vec![
+                "podman",
+                "run",
+                "--name",
+                "wildfly-26.1",
+                "-p",
+                "8261:8080",
+                "-p",
+                "9261:9990",
+                "quay.io/wildfly/wildfly:26.1.3.Final-jdk17",
+                "/opt/jboss/wildfly/bin/standalone.sh",
+                "-b",
+                "0.0.0.0",
+                "-bmanagement",
+                "0.0.0.0",
+            ],
+            spec.argv(Engine::Podman)
+        );
+    }
+
+    #[test]
+    fn docker_with_options() {
+        let instances = WildFlyInstance::enumeration("2x39").unwrap();
+        let mut spec = RunSpec::new(&instances[1], &VersionBased::default()).unwrap();
+        spec.published = BINDINGS.to_vec();
+        spec.platform = Some("linux/arm64".to_string());
+        spec.env = vec![("JAVA_OPTS".to_string(), "-Xmx1g".to_string())];
+        spec.args = vec!["--detach".to_string(), "--rm".to_string()];
+        spec.command = vec![];
+        assert_eq!(
+            vec![
+                "docker",
+                "run",
+                "--name",
+                "wildfly-39.0-1",
+                "--platform",
+                "linux/arm64",
+                "-p",
+                "14390:8080",
+                "-p",
+                "15390:9990",
+                "-p",
+                "16390:8443",
+                "-p",
+                "17390:8787",
+                "-p",
+                "18390:8009",
+                "-p",
+                "19390:9993",
+                "-e",
+                "JAVA_OPTS=-Xmx1g",
+                "--detach",
+                "--rm",
+                "quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21",
+            ],
+            spec.argv(Engine::Docker)
+        );
+    }
+
+    #[test]
+    fn enumeration() {
+        let instances = WildFlyInstance::enumeration("3x26.1,30..39").unwrap();
+        let specs = run_specs(&instances, &VersionBased::default()).unwrap();
+        assert_eq!(13, specs.len());
+        assert_eq!("wildfly-26.1-2", specs[2].name);
+        assert_eq!(20261, specs[2].ports.http);
+        assert_eq!(
+            "quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21",
+            specs[12].image
+        );
+    }
+
+    #[test]
+    fn dev_has_no_image() {
+        let instances = WildFlyInstance::enumeration("26.1,dev").unwrap();
+        assert!(run_specs(&instances, &VersionBased::default()).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+//! Helpers to render POSIX shell scripts.
+
+/// Quotes the given value with single quotes unless it only contains safe characters.
+pub(crate) fn quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@+,%".contains(c))
+    {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Quotes and joins the given arguments to a command line.
+pub(crate) fn command_line<S: AsRef<str>>(args: &[S]) -> String {
+    args.iter()
+        .map(|arg| quote(arg.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod shell_tests {
+    use crate::shell::{command_line, quote};
+
+    #[test]
+    fn quotes() {
+        assert_eq!("foo", quote("foo"));
+        assert_eq!(
+            "https://github.com/wildfly/wildfly.git",
+            quote("https://github.com/wildfly/wildfly.git")
+        );
+        assert_eq!("''", quote(""));
+        assert_eq!("'a b'", quote("a b"));
+        assert_eq!("'$HOME'", quote("$HOME"));
+        assert_eq!(r"'it'\''s'", quote("it's"));
+    }
+
+    #[test]
+    fn command_lines() {
+        assert_eq!("git clone 'a b'", command_line(&["git", "clone", "a b"]));
+    }
+}
@@ -0,0 +1,331 @@
+//! Generates systemd units which run [WildFlyInstance]s with Podman.
+//!
+//! [quadlets] renders Podman Quadlet `.container` files, which Podman turns into
+//! services (place them in `~/.config/containers/systemd/` or
+//! `/etc/containers/systemd/`). [services] renders plain `.service` units which call
+//! `podman run` directly. Both are based on the [RunSpec] of each instance.
+
+use crate::instance::WildFlyInstance;
+use crate::ports::PortStrategy;
+use crate::run::{run_specs, Engine, RunSpec};
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+/// The restart policy of the service
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Restart {
+    No,
+    OnFailure,
+    OnAbnormal,
+    Always,
+}
+
+impl Restart {
+    /// Returns the value of the `Restart=` setting.
+    pub fn value(&self) -> &'static str {
+        match self {
+            Restart::No => "no",
+            Restart::OnFailure => "on-failure",
+            Restart::OnAbnormal => "on-abnormal",
+            Restart::Always => "always",
+        }
+    }
+}
+
+/// Options for the generated units
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnitOptions {
+    /// The restart policy
+    pub restart: Restart,
+
+    /// The target which wants the service
+    pub wanted_by: String,
+
+    /// The path of the Podman executable (only used by [services])
+    pub podman: String,
+}
+
+impl Default for UnitOptions {
+    fn default() -> Self {
+        Self {
+            restart: Restart::Always,
+            wanted_by: "default.target".to_string(),
+            podman: "/usr/bin/podman".to_string(),
+        }
+    }
+}
+
+/// A generated unit file
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Unit {
+    /// The file name like "wildfly-26.1.container"
+    pub file_name: String,
+
+    /// The content of the unit file
+    pub content: String,
+}
+
+impl Unit {
+    /// Writes the unit file to the given directory.
+    pub fn write(&self, directory: &Path) -> Result<()> {
+        fs::write(directory.join(&self.file_name), &self.content)?;
+        Ok(())
+    }
+}
+
+/// Renders one Quadlet `.container` file per instance.
+///
+/// Fails for dev builds, local distributions and if the strategy fails to allocate ports.
+pub fn quadlets(
+    instances: &[WildFlyInstance],
+    strategy: &dyn PortStrategy,
+    options: &UnitOptions,
+) -> Result<Vec<Unit>> {
+    Ok(run_specs(instances, strategy)?
+        .iter()
+        .map(|spec| quadlet(spec, options))
+        .collect())
+}
+
+/// Renders one `.service` file per instance, which runs the container with `podman run`.
+///
+/// Fails for dev builds, local distributions and if the strategy fails to allocate ports.
+pub fn services(
+    instances: &[WildFlyInstance],
+    strategy: &dyn PortStrategy,
+    options: &UnitOptions,
+) -> Result<Vec<Unit>> {
+    Ok(run_specs(instances, strategy)?
+        .iter()
+        .map(|spec| service(spec, options))
+        .collect())
+}
+
+/// Renders the Quadlet `.container` file of a run specification.
+pub fn quadlet(spec: &RunSpec, options: &UnitOptions) -> Unit {
+    let mut lines = vec![
+        "[Unit]".to_string(),
+        format!("Description=WildFly container {}", spec.name),
+        String::new(),
+        "[Container]".to_string(),
+        format!("ContainerName={}", spec.name),
+        format!("Image={}", spec.image),
+    ];
+    for mapping in spec.mappings() {
+        lines.push(format!(
+            "PublishPort={}:{}",
+            mapping.host, mapping.container
+        ));
+    }
+    for (key, value) in &spec.env {
+        lines.push(format!(
+            "Environment={}",
+            quote(&format!("{}={}", key, value), false)
+        ));
+    }
+    let mut podman_args = vec![];
+    if let Some(platform) = &spec.platform {
+        podman_args.push(format!("--platform={}", platform));
+    }
+    podman_args.extend(spec.args.iter().cloned());
+    if !podman_args.is_empty() {
+        lines.push(format!("PodmanArgs={}", join(&podman_args)));
+    }
+    if !spec.command.is_empty() {
+        lines.push(format!("Exec={}", join(&spec.command)));
+    }
+    lines.extend(service_and_install(options, vec![]));
+    Unit {
+        file_name: format!("{}.container", spec.name),
+        content: lines.join("\n"),
+    }
+}
+
+/// Renders the plain `.service` file of a run specification.
+pub fn service(spec: &RunSpec, options: &UnitOptions) -> Unit {
+    let mut run = spec.argv(Engine::Podman);
+    run[0] = options.podman.clone();
+    // remove the container when it stops, so that the next start can reuse the name
+    run.insert(2, "--rm".to_string());
+    let mut lines = vec![
+        "[Unit]".to_string(),
+        format!("Description=WildFly container {}", spec.name),
+        "Wants=network-online.target".to_string(),
+        "After=network-online.target".to_string(),
+    ];
+    lines.extend(service_and_install(
+        options,
+        vec![
+            format!(
+                "ExecStartPre=-{} rm --force --ignore {}",
+                options.podman,
+                quote(&spec.name, true)
+            ),
+            format!("ExecStart={}", join(&run)),
+            format!(
+                "ExecStop={} stop --ignore {}",
+                options.podman,
+                quote(&spec.name, true)
+            ),
+        ],
+    ));
+    Unit {
+        file_name: format!("{}.service", spec.name),
+        content: lines.join("\n"),
+    }
+}
+
+fn service_and_install(options: &UnitOptions, service: Vec<String>) -> Vec<String> {
+    let mut lines = vec![String::new(), "[Service]".to_string()];
+    lines.extend(service);
+    lines.push(format!("Restart={}", options.restart.value()));
+    lines.push(String::new());
+    lines.push("[Install]".to_string());
+    lines.push(format!("WantedBy={}", options.wanted_by));
+    lines.push(String::new());
+    lines
+}
+
+fn join(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| quote(arg, true))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quotes a value for unit files and escapes specifiers (`%`) and,
+/// for command lines, variable expansion (`$`).
+fn quote(value: &str, command_line: bool) -> String {
+    let mut escaped = value.replace('%', "%%");
+    if command_line {
+        escaped = escaped.replace('$', "$$");
+    }
+    if !escaped.is_empty()
+        && !escaped
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';'))
+    {
+        escaped
+    } else {
+        format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod systemd_tests {
+    use crate::instance::WildFlyInstance;
+    use crate::ports::VersionBased;
+    use crate::run::RunSpec;
+    use crate::systemd::{quadlet, quadlets, quote, services, Restart, UnitOptions};
+
+    #[test]
+    fn quadlet_container() {
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        let units = quadlets(
+            &instances,
+            &VersionBased::default(),
+            &UnitOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(1, units.len());
+        assert_eq!("wildfly-26.1.container", units[0].file_name);
+        assert_eq!(
+            r#"[Unit]
+Description=WildFly container wildfly-26.1
+
+[Container]
+ContainerName=wildfly-26.1
+Image=quay.io/wildfly/wildfly:26.1.3.Final-jdk17
+PublishPort=8261:8080
+PublishPort=9261:9990
+Exec=/opt/jboss/wildfly/bin/standalone.sh -b 0.0.0.0 -bmanagement 0.0.0.0
+
+[Service]
+Restart=always
+
+[Install]
+WantedBy=default.target
+"#,
+            units[0].content
+        );
+    }
+
+    #[test]
+    fn quadlet_options() {
+        let instances = WildFlyInstance::enumeration("2x39").unwrap();
+        let mut spec = RunSpec::new(&instances[1], &VersionBased::default()).unwrap();
+        spec.platform = Some("linux/arm64".to_string());
+        spec.env = vec![("JAVA_OPTS".to_string(), "-Xms64m -Xmx1g".to_string())];
+        spec.args = vec!["--memory=2g".to_string()];
+        let options = UnitOptions {
+            restart: Restart::OnFailure,
+            wanted_by: "multi-user.target".to_string(),
+            ..UnitOptions::default()
+        };
+        let unit = quadlet(&spec, &options);
+        assert_eq!("wildfly-39.0-1.container", unit.file_name);
+        assert!(unit.content.contains("PublishPort=14390:8080\n"));
+        assert!(unit
+            .content
+            .contains("Environment=\"JAVA_OPTS=-Xms64m -Xmx1g\"\n"));
+        assert!(unit
+            .content
+            .contains("PodmanArgs=--platform=linux/arm64 --memory=2g\n"));
+        assert!(unit.content.contains("Restart=on-failure\n"));
+        assert!(unit.content.contains("WantedBy=multi-user.target\n"));
+    }
+
+    #[test]
+    fn plain_service() {
+        let instances = WildFlyInstance::enumeration("26.1").unwrap();
+        let units = services(
+            &instances,
+            &VersionBased::default(),
+            &UnitOptions::default(),
+        )
+        .unwrap();
+        assert_eq!("wildfly-26.1.service", units[0].file_name);
+        assert_eq!(
+            r#"[Unit]
+Description=WildFly container wildfly-26.1
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+ExecStartPre=-/usr/bin/podman rm --force --ignore wildfly-26.1
+ExecStart=/usr/bin/podman run --rm --name wildfly-26.1 -p 8261:8080 -p 9261:9990 quay.io/wildfly/wildfly:26.1.3.Final-jdk17 /opt/jboss/wildfly/bin/standalone.sh -b 0.0.0.0 -bmanagement 0.0.0.0
+ExecStop=/usr/bin/podman stop --ignore wildfly-26.1
+Restart=always
+
+[Install]
+WantedBy=default.target
+"#,
+            units[0].content
+        );
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!("foo", quote("foo", true));
+        assert_eq!("\"\"", quote("", true));
+        assert_eq!("\"a b\"", quote("a b", true));
+        assert_eq!("\"a \\\"b\\\"\"", quote("a \"b\"", true));
+        assert_eq!("100%%", quote("100%", false));
+        assert_eq!("$$HOME", quote("$HOME", true));
+        assert_eq!("$HOME", quote("$HOME", false));
+    }
+
+    #[test]
+    fn dev_has_no_image() {
+        let instances = WildFlyInstance::enumeration("dev").unwrap();
+        assert!(quadlets(
+            &instances,
+            &VersionBased::default(),
+            &UnitOptions::default()
+        )
+        .is_err());
+    }
+}
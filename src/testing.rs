@@ -0,0 +1,103 @@
+//! Helpers shared by the tests of several modules.
+
+use crate::ports::{PortStrategy, Ports};
+use crate::WildFlyContainer;
+use anyhow::Result;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// The timeout of requests to stub servers
+pub(crate) const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serializes the tests which change or depend on the global mirror table
+pub(crate) static MIRRORS_LOCK: Mutex<()> = Mutex::new(());
+
+/// A port strategy which returns the same ports for all containers
+pub(crate) struct Fixed(pub(crate) Ports);
+
+impl PortStrategy for Fixed {
+    fn ports(&self, _wildfly: &WildFlyContainer) -> Result<Ports> {
+        Ok(self.0)
+    }
+}
+
+/// A response of a stub server: status, headers and body
+pub(crate) type Response = (&'static str, Vec<(String, String)>, String);
+
+/// Starts a stub HTTP server which answers each request with the response of the handler
+/// and returns its URL. The handler gets the request line and headers.
+pub(crate) fn stub(handler: impl Fn(&str) -> Response + Send + 'static) -> String {
+    let (listener, url) = bind();
+    serve(listener, handler);
+    url
+}
+
+/// Binds a stub server to a free port and returns its URL.
+pub(crate) fn bind() -> (TcpListener, String) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+    (listener, url)
+}
+
+/// Answers the requests to a bound stub server with the responses of the handler.
+pub(crate) fn serve(listener: TcpListener, handler: impl Fn(&str) -> Response + Send + 'static) {
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            while let Ok(n) = stream.read(&mut buffer) {
+                request.extend_from_slice(&buffer[..n]);
+                if n == 0 || request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    break;
+                }
+            }
+            let (status, headers, body) = handler(&String::from_utf8_lossy(&request));
+            let mut response = format!("HTTP/1.1 {}\r\n", status);
+            for (name, value) in headers {
+                response.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            response.push_str(&format!(
+                "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            ));
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+}
+
+/// Returns the port of a stub server URL.
+pub(crate) fn port(url: &str) -> u16 {
+    url.rsplit(':').next().unwrap().parse().unwrap()
+}
+
+/// Returns a port which nobody listens on.
+pub(crate) fn closed_port() -> u16 {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+pub(crate) fn ok(body: &str) -> Response {
+    ok_with(vec![], body)
+}
+
+pub(crate) fn ok_with(headers: Vec<(&str, String)>, body: &str) -> Response {
+    (
+        "200 OK",
+        headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+        body.to_string(),
+    )
+}
+
+pub(crate) fn not_found() -> Response {
+    ("404 Not Found", vec![], String::new())
+}
@@ -0,0 +1,396 @@
+//! Detects upstream releases which are not yet in the catalog.
+//!
+//! [check] lists the tags of the repositories used in the catalog (`GET /v2/<name>/tags/list`),
+//! parses the release tags like "39.0.1.Final-2-jdk21" and reports
+//!
+//! - new releases: versions newer than the catalog version of their minor,
+//!   or minors which are not in the catalog,
+//! - rebuilds: newer builds of a catalog version for the same JDK,
+//!   like "39.0.1.Final-2-jdk21" for "39.0.1.Final-jdk21",
+//! - JDK variants: tags of a catalog version for a newer JDK than the catalog uses.
+//!
+//! Tags of a catalog version are compared only if they come from the repository of that version,
+//! tags of new minors only if they come from the repository of the previous catalog version.
+//! Only final releases are considered. Versions older than the oldest catalog version,
+//! floating tags like "latest" and pre-releases like "40.0.0.Beta1-jdk21" are ignored.
+
+use crate::image::ImageReference;
+use crate::registry::Registry;
+use crate::{WildFlyContainer, VERSIONS};
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::Version;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+const FINAL: &str = "Final";
+
+lazy_static! {
+    static ref RELEASE_TAG_RE: Regex = Regex::new(
+        r"^(?<major>[0-9]+)\.(?<minor>[0-9]+)\.(?<patch>[0-9]+)\.(?<qualifier>[A-Za-z]+[0-9]*)(-(?<build>[0-9]+))?(-jdk(?<jdk>[0-9]+))?$"
+    )
+    .unwrap();
+}
+
+/// A parsed release tag like "39.0.1.Final-2-jdk21"
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReleaseTag {
+    /// The repository of the tag like "quay.io/wildfly/wildfly"
+    pub repository: String,
+
+    /// The tag like "39.0.1.Final-2-jdk21"
+    pub name: String,
+
+    /// The version like 39.0.1
+    pub version: Version,
+
+    /// The qualifier like "Final" or "Beta1"
+    pub qualifier: String,
+
+    /// The build number: 2 for "39.0.1.Final-2-jdk21", 1 for the initial build
+    pub build: u32,
+
+    /// The JDK like 21 for "39.0.1.Final-2-jdk21", `None` for tags without JDK
+    pub jdk: Option<u32>,
+}
+
+impl ReleaseTag {
+    /// Parses a tag of the given repository. Returns `None` for tags which are no release
+    /// tags like "latest" or "latest-jdk21".
+    pub fn parse(repository: &str, tag: &str) -> Option<ReleaseTag> {
+        let c = RELEASE_TAG_RE.captures(tag)?;
+        Some(ReleaseTag {
+            repository: repository.to_string(),
+            name: tag.to_string(),
+            version: Version::new(
+                c["major"].parse().ok()?,
+                c["minor"].parse().ok()?,
+                c["patch"].parse().ok()?,
+            ),
+            qualifier: c["qualifier"].to_string(),
+            build: match c.name("build") {
+                Some(build) => build.as_str().parse().ok()?,
+                None => 1,
+            },
+            jdk: match c.name("jdk") {
+                Some(jdk) => Some(jdk.as_str().parse().ok()?),
+                None => None,
+            },
+        })
+    }
+
+    /// Returns the suffix of the tag like "Final-2-jdk21" (see [WildFlyContainer::suffix]).
+    pub fn suffix(&self) -> &str {
+        &self.name[self.version.to_string().len() + 1..]
+    }
+
+    /// Returns the image name like "quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21".
+    pub fn image_name(&self) -> String {
+        format!("{}:{}", self.repository, self.name)
+    }
+}
+
+/// An upstream image which is not in the catalog
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Update {
+    /// A version which is newer than the catalog version of its minor
+    /// or whose minor is not in the catalog.
+    Release(ReleaseTag),
+
+    /// A newer build of a catalog version for the same JDK.
+    Rebuild(ReleaseTag),
+
+    /// A catalog version for a newer JDK than the catalog uses.
+    Jdk(ReleaseTag),
+}
+
+impl Update {
+    /// Returns the tag of the update.
+    pub fn tag(&self) -> &ReleaseTag {
+        match self {
+            Update::Release(tag) | Update::Rebuild(tag) | Update::Jdk(tag) => tag,
+        }
+    }
+}
+
+impl Display for Update {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Update::Release(tag) => write!(f, "new release {}", tag.image_name()),
+            Update::Rebuild(tag) => write!(f, "rebuild {}", tag.image_name()),
+            Update::Jdk(tag) => write!(f, "new JDK variant {}", tag.image_name()),
+        }
+    }
+}
+
+/// Checks the repositories of the catalog versions for updates.
+///
+/// `timeout` applies to each request.
+pub fn check(timeout: Duration) -> Result<Vec<Update>> {
+    let catalog = VERSIONS.values().cloned().collect::<Vec<_>>();
+    check_catalog(&Registry::new(timeout), &catalog)
+}
+
+/// Checks the repositories of the given catalog for updates using the given registry client.
+pub fn check_catalog(registry: &Registry, catalog: &[WildFlyContainer]) -> Result<Vec<Update>> {
+    let mut repositories: Vec<&str> = vec![];
+    for wildfly in catalog.iter().filter(|w| released(w)) {
+        if !repositories.contains(&wildfly.repository.as_str()) {
+            repositories.push(&wildfly.repository);
+        }
+    }
+    let mut tags = vec![];
+    for repository in repositories {
+        let image = ImageReference::parse(repository)?;
+        let names = registry
+            .tags(&image)
+            .map_err(|e| anyhow!("unable to list the tags of {}: {}", repository, e))?;
+        tags.extend(
+            names
+                .iter()
+                .filter_map(|name| ReleaseTag::parse(repository, name)),
+        );
+    }
+    Ok(updates(catalog, &tags))
+}
+
+/// Compares the given tags with the catalog and returns the updates ordered by version.
+pub fn updates(catalog: &[WildFlyContainer], tags: &[ReleaseTag]) -> Vec<Update> {
+    let catalog = catalog.iter().filter(|w| released(w)).collect::<Vec<_>>();
+    let Some(oldest) = catalog.iter().map(|w| &w.version).min() else {
+        return vec![];
+    };
+    let mut minors: BTreeMap<(u64, u64), Vec<&ReleaseTag>> = BTreeMap::new();
+    for tag in tags
+        .iter()
+        .filter(|t| t.qualifier == FINAL && t.version >= *oldest)
+    {
+        minors
+            .entry((tag.version.major, tag.version.minor))
+            .or_default()
+            .push(tag);
+    }
+
+    let mut updates = vec![];
+    for ((major, minor), tags) in minors {
+        let wildfly = catalog
+            .iter()
+            .find(|w| w.version.major == major && w.version.minor == minor);
+        // catalog versions are compared with their own repository,
+        // new minors with the repository of the previous catalog version
+        let repository = match wildfly {
+            Some(wildfly) => &wildfly.repository,
+            None => match catalog
+                .iter()
+                .filter(|w| (w.version.major, w.version.minor) < (major, minor))
+                .max_by(|a, b| a.version.cmp(&b.version))
+            {
+                Some(previous) => &previous.repository,
+                None => continue,
+            },
+        };
+        let tags = tags
+            .into_iter()
+            .filter(|t| t.repository == *repository)
+            .collect::<Vec<_>>();
+        let Some(newest) = tags.iter().map(|t| &t.version).max() else {
+            continue;
+        };
+        match wildfly {
+            Some(wildfly) if wildfly.version >= *newest => {
+                let current = format!("{}.{}", wildfly.version, wildfly.suffix);
+                let Some(current) = ReleaseTag::parse(&wildfly.repository, &current) else {
+                    continue;
+                };
+                let builds = tags
+                    .iter()
+                    .filter(|t| t.version == current.version)
+                    .collect::<Vec<_>>();
+                if let Some(rebuild) = builds
+                    .iter()
+                    .filter(|t| t.jdk == current.jdk && t.build > current.build)
+                    .max_by_key(|t| t.build)
+                {
+                    updates.push(Update::Rebuild((**rebuild).clone()));
+                }
+                let mut jdks = builds
+                    .iter()
+                    .filter_map(|t| t.jdk)
+                    // images without a JDK in the tag have no JDK to upgrade
+                    .filter(|jdk| current.jdk.is_some_and(|current| *jdk > current))
+                    .collect::<Vec<_>>();
+                jdks.sort();
+                jdks.dedup();
+                for jdk in jdks {
+                    if let Some(variant) = builds
+                        .iter()
+                        .filter(|t| t.jdk == Some(jdk))
+                        .max_by_key(|t| t.build)
+                    {
+                        updates.push(Update::Jdk((**variant).clone()));
+                    }
+                }
+            }
+            _ => {
+                // the latest build for the newest JDK
+                if let Some(release) = tags
+                    .iter()
+                    .filter(|t| t.version == *newest)
+                    .max_by_key(|t| (t.jdk, t.build))
+                {
+                    updates.push(Update::Release((*release).clone()));
+                }
+            }
+        }
+    }
+    updates
+}
+
+fn released(wildfly: &WildFlyContainer) -> bool {
+    wildfly.has_image() && wildfly.custom_image.is_none()
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod updates_tests {
+    use crate::registry::Registry;
+    use crate::testing::{not_found, ok, stub, TIMEOUT};
+    use crate::updates::{check_catalog, updates, ReleaseTag, Update};
+    use crate::VERSIONS;
+    use semver::Version;
+
+    const QUAY_TAGS: [&str; 12] = [
+        "latest",
+        "latest-jdk21",
+        "26.1.3.Final-jdk11",
+        "26.1.3.Final-jdk17",
+        "38.0.0.Final-jdk21",
+        "39.0.1.Final-jdk21",
+        "39.0.1.Final-2-jdk21",
+        "39.0.1.Final-3-jdk21",
+        "39.0.1.Final-jdk25",
+        "40.0.0.Beta1-jdk21",
+        "40.0.0.Final-jdk21",
+        "40.0.0.Final-jdk25",
+    ];
+
+    #[test]
+    fn parse() {
+        let tag = ReleaseTag::parse("quay.io/wildfly/wildfly", "39.0.1.Final-2-jdk21").unwrap();
+        assert_eq!(Version::new(39, 0, 1), tag.version);
+        assert_eq!("Final", tag.qualifier);
+        assert_eq!(2, tag.build);
+        assert_eq!(Some(21), tag.jdk);
+        assert_eq!("Final-2-jdk21", tag.suffix());
+        assert_eq!(
+            "quay.io/wildfly/wildfly:39.0.1.Final-2-jdk21",
+            tag.image_name()
+        );
+
+        let tag = ReleaseTag::parse("docker.io/jboss/wildfly", "20.0.1.Final").unwrap();
+        assert_eq!(1, tag.build);
+        assert_eq!(None, tag.jdk);
+        assert_eq!("Final", tag.suffix());
+
+        let tag = ReleaseTag::parse("quay.io/wildfly/wildfly", "40.0.0.Beta1-jdk21").unwrap();
+        assert_eq!("Beta1", tag.qualifier);
+        assert!(ReleaseTag::parse("quay.io/wildfly/wildfly", "latest").is_none());
+        assert!(ReleaseTag::parse("quay.io/wildfly/wildfly", "latest-jdk21").is_none());
+        assert!(ReleaseTag::parse("quay.io/wildfly/wildfly", "39.0.Final").is_none());
+    }
+
+    #[test]
+    fn compare() {
+        let catalog = VERSIONS.values().cloned().collect::<Vec<_>>();
+        let tags = QUAY_TAGS
+            .iter()
+            .filter_map(|tag| ReleaseTag::parse("quay.io/wildfly/wildfly", tag))
+            .chain(ReleaseTag::parse("docker.io/jboss/wildfly", "8.2.1.Final"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "rebuild quay.io/wildfly/wildfly:39.0.1.Final-3-jdk21",
+                "new JDK variant quay.io/wildfly/wildfly:39.0.1.Final-jdk25",
+                "new release quay.io/wildfly/wildfly:40.0.0.Final-jdk25",
+            ],
+            updates(&catalog, &tags)
+                .iter()
+                .map(Update::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn new_patch() {
+        let catalog = VERSIONS.values().cloned().collect::<Vec<_>>();
+        let tags = [
+            "26.1.3.Final-jdk17",
+            "26.1.4.Final-jdk11",
+            "26.1.4.Final-jdk17",
+        ]
+        .iter()
+        .filter_map(|tag| ReleaseTag::parse("quay.io/wildfly/wildfly", tag))
+        .collect::<Vec<_>>();
+        let updates = updates(&catalog, &tags);
+        assert_eq!(1, updates.len());
+        assert!(matches!(&updates[0], Update::Release(tag) if tag.name == "26.1.4.Final-jdk17"));
+    }
+
+    #[test]
+    fn final_without_jdk() {
+        let catalog = VERSIONS.values().cloned().collect::<Vec<_>>();
+        let tags = ["20.0.1.Final", "20.0.1.Final-jdk11"]
+            .iter()
+            .filter_map(|tag| ReleaseTag::parse("docker.io/jboss/wildfly", tag))
+            .collect::<Vec<_>>();
+        assert!(updates(&catalog, &tags).is_empty());
+    }
+
+    #[test]
+    fn other_repository() {
+        let catalog = VERSIONS.values().cloned().collect::<Vec<_>>();
+        let tags = [
+            ("docker.io/jboss/wildfly", "26.1.4.Final"),
+            ("docker.io/jboss/wildfly", "40.0.0.Final"),
+            ("quay.io/wildfly/wildfly", "20.0.2.Final"),
+            ("docker.io/jboss/wildfly", "20.0.2.Final"),
+        ]
+        .iter()
+        .filter_map(|(repository, tag)| ReleaseTag::parse(repository, tag))
+        .collect::<Vec<_>>();
+        let updates = updates(&catalog, &tags);
+        assert_eq!(1, updates.len());
+        assert_eq!("docker.io/jboss/wildfly", updates[0].tag().repository);
+        assert_eq!("20.0.2.Final", updates[0].tag().name);
+    }
+
+    #[test]
+    fn check_registry() {
+        let url = stub(|request| {
+            if request.starts_with("GET /v2/wildfly/wildfly/tags/list ") {
+                ok(&format!(
+                    r#"{{"name":"wildfly/wildfly","tags":{}}}"#,
+                    serde_json::to_string(&QUAY_TAGS).unwrap()
+                ))
+            } else if request.starts_with("GET /v2/jboss/wildfly/tags/list ") {
+                ok(r#"{"name":"jboss/wildfly","tags":["latest","8.2.1.Final","20.0.1.Final"]}"#)
+            } else {
+                not_found()
+            }
+        });
+        let registry = Registry::new(TIMEOUT)
+            .with_endpoint("quay.io", &url)
+            .with_endpoint("docker.io", &url);
+        let catalog = VERSIONS.values().cloned().collect::<Vec<_>>();
+        let updates = check_catalog(&registry, &catalog).unwrap();
+        assert_eq!(3, updates.len());
+        assert_eq!("40.0.0.Final-jdk25", updates[2].tag().name);
+
+        let registry = Registry::new(TIMEOUT).with_endpoint("quay.io", &url);
+        let catalog = vec![crate::custom::custom_image("localhost/wildfly:39", None).unwrap()];
+        assert!(check_catalog(&registry, &catalog).unwrap().is_empty());
+    }
+}
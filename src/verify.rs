@@ -0,0 +1,367 @@
+//! Verifies that the images of the catalog exist in their registries.
+//!
+//! [verify] reads the manifest of [WildFlyContainer::image_name] for each catalog version
+//! and compares the advertised platforms with [WildFlyContainer::platforms]. Versions without
+//! platforms are expected to provide [DEFAULT_PLATFORM] only. An advertised platform with a
+//! variant like "linux/arm64/v8" matches the platform "linux/arm64". Known digests
+//! ([WildFlyContainer::digests]) have to match the digests of the platform manifests.
+//! [fetch_digests] reads these digests for a [Digests] table.
+
+use crate::digests::Digests;
+use crate::image::{matches_platform, ImageReference, DEFAULT_PLATFORM};
+use crate::registry::{Manifest, Registry};
+use crate::{WildFlyContainer, VERSIONS};
+use anyhow::{bail, Result};
+use std::fmt::{Display, Formatter};
+use std::thread;
+use std::time::Duration;
+
+/// The result of a verification
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Status {
+    /// The image exists and advertises the expected platforms.
+    Ok,
+
+    /// The registry doesn't know the image.
+    Missing,
+
+    /// The advertised platforms differ from the expected platforms.
+    Platforms {
+        /// Expected platforms which the image doesn't advertise
+        missing: Vec<String>,
+
+        /// Advertised platforms which are not expected
+        unexpected: Vec<String>,
+    },
+
+    /// The digests of the given platforms differ from the known digests.
+    Digests(Vec<String>),
+
+    /// The registry couldn't be asked.
+    Failed(String),
+}
+
+/// The verification of a catalog version
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Verification {
+    /// The verified version
+    pub wildfly: WildFlyContainer,
+
+    /// The verified image
+    pub image: String,
+
+    /// The result of the verification
+    pub status: Status,
+}
+
+impl Verification {
+    /// Returns `true` if the image exists and matches the catalog.
+    pub fn is_ok(&self) -> bool {
+        self.status == Status::Ok
+    }
+}
+
+impl Display for Verification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.image)?;
+        match &self.status {
+            Status::Ok => write!(f, "ok"),
+            Status::Missing => write!(f, "not found"),
+            Status::Platforms {
+                missing,
+                unexpected,
+            } => {
+                let mut problems = vec![];
+                if !missing.is_empty() {
+                    problems.push(format!("missing platforms {}", missing.join(", ")));
+                }
+                if !unexpected.is_empty() {
+                    problems.push(format!("unexpected platforms {}", unexpected.join(", ")));
+                }
+                write!(f, "{}", problems.join("; "))
+            }
+            Status::Digests(platforms) => {
+                write!(f, "different digests for {}", platforms.join(", "))
+            }
+            Status::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+/// Verifies the images of all catalog versions.
+///
+/// `timeout` applies to each request.
+pub fn verify(timeout: Duration) -> Vec<Verification> {
+    let catalog = VERSIONS.values().cloned().collect::<Vec<_>>();
+    verify_catalog(&Registry::new(timeout), &catalog)
+}
+
+/// Verifies the images of the given versions using the given registry client.
+///
+/// The verifications run in parallel. Dev builds and local distributions have no image
+/// and are skipped.
+pub fn verify_catalog(registry: &Registry, catalog: &[WildFlyContainer]) -> Vec<Verification> {
+    thread::scope(|scope| {
+        let handles = catalog
+            .iter()
+            .filter(|wildfly| wildfly.has_image())
+            .map(|wildfly| scope.spawn(move || verify_image(registry, wildfly)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok())
+            .collect()
+    })
+}
+
+/// Renders one line per verification followed by a summary.
+pub fn report(verifications: &[Verification]) -> String {
+    let failures = verifications.iter().filter(|v| !v.is_ok()).count();
+    let mut report = verifications
+        .iter()
+        .map(|v| format!("{}\n", v))
+        .collect::<String>();
+    report.push_str(&format!(
+        "{} images verified, {} failed\n",
+        verifications.len(),
+        failures
+    ));
+    report
+}
+
+/// Reads the digests of the given containers from their registries.
+///
+/// The digests are stored for the platforms of the catalog, an advertised platform
+/// like "linux/arm64/v8" for "linux/arm64". Containers without an image are skipped.
+pub fn fetch_digests(registry: &Registry, containers: &[WildFlyContainer]) -> Result<Digests> {
+    let mut digests = Digests::default();
+    for wildfly in containers.iter().filter(|w| w.has_image()) {
+        let image = wildfly.upstream_image_name();
+        let reference = ImageReference::parse(&image)?;
+        let Some(manifest) = registry.manifest(&reference)? else {
+            bail!("image {} not found", image)
+        };
+        let platforms = if wildfly.platforms.is_empty() {
+            vec![DEFAULT_PLATFORM.to_string()]
+        } else {
+            wildfly.platforms.clone()
+        };
+        for platform in platforms {
+            if let Some((_, digest)) = manifest
+                .digests
+                .iter()
+                .find(|(advertised, _)| matches_platform(advertised, &platform))
+            {
+                digests = digests.with(&image, &platform, digest);
+            }
+        }
+    }
+    Ok(digests)
+}
+
+fn verify_image(registry: &Registry, wildfly: &WildFlyContainer) -> Verification {
+    let image = wildfly.image_name();
+    let status = match wildfly.image_reference() {
+        Some(reference) => match registry.manifest(&reference) {
+            Ok(Some(manifest)) => compare(wildfly, &manifest),
+            Ok(None) => Status::Missing,
+            Err(e) => Status::Failed(e.to_string()),
+        },
+        None => Status::Failed(format!("invalid image {}", image)),
+    };
+    Verification {
+        wildfly: wildfly.clone(),
+        image,
+        status,
+    }
+}
+
+fn compare(wildfly: &WildFlyContainer, manifest: &Manifest) -> Status {
+    let expected = if wildfly.platforms.is_empty() {
+        vec![DEFAULT_PLATFORM.to_string()]
+    } else {
+        wildfly.platforms.clone()
+    };
+    let advertised = |platform: &str| {
+        manifest
+            .platforms
+            .iter()
+            .find(|p| matches_platform(p, platform))
+            .cloned()
+    };
+    let missing = expected
+        .iter()
+        .filter(|platform| advertised(platform).is_none())
+        .cloned()
+        .collect::<Vec<_>>();
+    let unexpected = manifest
+        .platforms
+        .iter()
+        .filter(|p| {
+            !expected
+                .iter()
+                .any(|platform| matches_platform(p, platform))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() || !unexpected.is_empty() {
+        return Status::Platforms {
+            missing,
+            unexpected,
+        };
+    }
+    let different = wildfly
+        .digests
+        .iter()
+        .filter(|(platform, digest)| {
+            advertised(platform).and_then(|p| manifest.digests.get(&p)) != Some(*digest)
+        })
+        .map(|(platform, _)| platform.clone())
+        .collect::<Vec<_>>();
+    if different.is_empty() {
+        Status::Ok
+    } else {
+        Status::Digests(different)
+    }
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod verify_tests {
+    use crate::registry::Registry;
+    use crate::testing::{not_found, ok, ok_with, stub, TIMEOUT};
+    use crate::verify::{fetch_digests, report, verify_catalog, Status};
+    use crate::WildFlyContainer;
+
+    const DIGEST: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    const OTHER_DIGEST: &str =
+        "sha256:fedcba9876543210fedcba9876543210fedcba9876543210fedcba9876543210";
+
+    fn index(platforms: &[&str]) -> String {
+        let manifests = platforms
+            .iter()
+            .map(|platform| {
+                let mut segments = platform.split('/');
+                format!(
+                    r#"{{"digest":"{}","platform":{{"os":"{}","architecture":"{}"}}}}"#,
+                    DIGEST,
+                    segments.next().unwrap(),
+                    segments.next().unwrap()
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(r#"{{"manifests":[{}]}}"#, manifests.join(","))
+    }
+
+    fn registry() -> Registry {
+        let url = stub(|request| {
+            if request.starts_with("GET /v2/wildfly/wildfly/manifests/39.0.1.Final-2-jdk21 ") {
+                ok(&index(&[
+                    "linux/amd64",
+                    "linux/arm64",
+                    "linux/s390x",
+                    "linux/ppc64le",
+                ]))
+            } else if request.starts_with("GET /v2/wildfly/wildfly/manifests/26.1.3.Final-jdk17 ") {
+                ok(&index(&["linux/amd64", "linux/ppc64le"]))
+            } else if request.starts_with("GET /v2/jboss/wildfly/manifests/20.0.1.Final ") {
+                ok_with(
+                    vec![("Docker-Content-Digest", DIGEST.to_string())],
+                    r#"{"config":{"digest":"sha256:config"}}"#,
+                )
+            } else if request.starts_with("GET /v2/jboss/wildfly/blobs/sha256:config ") {
+                ok(r#"{"architecture":"amd64","os":"linux"}"#)
+            } else if request.starts_with("GET /v2/jboss/wildfly/manifests/19.1.0.Final ") {
+                ("500 Internal Server Error", vec![], String::new())
+            } else {
+                not_found()
+            }
+        });
+        Registry::new(TIMEOUT)
+            .with_endpoint("quay.io", &url)
+            .with_endpoint("docker.io", &url)
+    }
+
+    #[test]
+    fn verify() {
+        let catalog = WildFlyContainer::enumeration("19.1,20,26.1,39,dev").unwrap();
+        let mut catalog = [catalog, WildFlyContainer::enumeration("38").unwrap()].concat();
+        let wf39 = catalog
+            .iter_mut()
+            .find(|wildfly| wildfly.short_version == "39.0")
+            .unwrap();
+        *wf39 = wf39.clone().with_digest("linux/arm64", DIGEST);
+        let verifications = verify_catalog(&registry(), &catalog);
+        assert_eq!(5, verifications.len());
+        let status = |version: &str| {
+            verifications
+                .iter()
+                .find(|v| v.wildfly.short_version == version)
+                .map(|v| v.status.clone())
+                .unwrap()
+        };
+        assert!(matches!(status("19.1"), Status::Failed(_)));
+        assert_eq!(Status::Ok, status("20.0"));
+        assert_eq!(
+            Status::Platforms {
+                missing: vec!["linux/arm64".to_string()],
+                unexpected: vec!["linux/ppc64le".to_string()],
+            },
+            status("26.1")
+        );
+        assert_eq!(Status::Ok, status("39.0"));
+        assert_eq!(Status::Missing, status("38.0"));
+
+        let report = report(&verifications);
+        assert!(report.contains(
+            "quay.io/wildfly/wildfly:26.1.3.Final-jdk17: missing platforms linux/arm64; unexpected platforms linux/ppc64le\n"
+        ));
+        assert!(report.contains("docker.io/jboss/wildfly:20.0.1.Final: ok\n"));
+        assert!(report.contains("quay.io/wildfly/wildfly:38.0.1.Final-jdk21: not found\n"));
+        assert!(report.ends_with("5 images verified, 3 failed\n"));
+    }
+
+    #[test]
+    fn digests() {
+        let wildfly = WildFlyContainer::version("39")
+            .unwrap()
+            .with_digest("linux/arm64", DIGEST)
+            .with_digest("linux/amd64", OTHER_DIGEST);
+        let verifications = verify_catalog(&registry(), &[wildfly]);
+        assert_eq!(
+            Status::Digests(vec!["linux/amd64".to_string()]),
+            verifications[0].status
+        );
+        assert!(verifications[0]
+            .to_string()
+            .ends_with("different digests for linux/amd64"));
+    }
+
+    #[test]
+    fn fetch() {
+        let url = stub(|request| {
+            if request.starts_with("GET /v2/wildfly/wildfly/manifests/36.0.1.Final-jdk21 ") {
+                ok(&format!(
+                    r#"{{"manifests":[
+                        {{"digest":"{}","platform":{{"os":"linux","architecture":"amd64"}}}},
+                        {{"digest":"{}","platform":{{"os":"linux","architecture":"arm64","variant":"v8"}}}}]}}"#,
+                    DIGEST, OTHER_DIGEST
+                ))
+            } else {
+                not_found()
+            }
+        });
+        let registry = Registry::new(TIMEOUT).with_endpoint("quay.io", &url);
+        let wildfly = WildFlyContainer::version("36").unwrap();
+        let digests = fetch_digests(&registry, std::slice::from_ref(&wildfly)).unwrap();
+        let platforms = digests
+            .get("quay.io/wildfly/wildfly:36.0.1.Final-jdk21")
+            .unwrap();
+        assert_eq!(2, platforms.len());
+        assert_eq!(OTHER_DIGEST, platforms["linux/arm64"]);
+
+        let wildfly = WildFlyContainer::version("35").unwrap();
+        assert!(fetch_digests(&registry, &[wildfly]).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+//! Helpers to render YAML documents without a YAML library.
+//!
+//! Scalars are written as JSON strings, which are valid YAML double-quoted scalars.
+
+/// Quotes and escapes the given value.
+pub(crate) fn quote(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+/// Renders the given values as YAML flow sequence like `["a", "b"]`.
+pub(crate) fn flow_sequence<S: AsRef<str>>(values: &[S]) -> String {
+    format!(
+        "[{}]",
+        values
+            .iter()
+            .map(|value| quote(value.as_ref()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+// ------------------------------------------------------ tests
+
+#[cfg(test)]
+mod yaml_tests {
+    use crate::yaml::{flow_sequence, quote};
+
+    #[test]
+    fn quotes() {
+        assert_eq!(r#""foo""#, quote("foo"));
+        assert_eq!(r#""a \"b\"\nc""#, quote("a \"b\"\nc"));
+    }
+
+    #[test]
+    fn flow_sequences() {
+        assert_eq!("[]", flow_sequence::<&str>(&[]));
+        assert_eq!(r#"["a", "b"]"#, flow_sequence(&["a", "b"]));
+    }
+}